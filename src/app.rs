@@ -1,9 +1,30 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    event::{AppEvent, Event, EventHandler}, object::{ObjectHandle, Parent}, space::{Galaxy, World},
+    change::{Change, Subscription},
+    event::{AppEvent, Event, EventHandler, TICK_FPS},
+    faction::{Owner, Standing},
+    keymap::{Action, Keymap},
+    message::{Message, Severity},
+    market::TradeError,
+    object::{ComponentKind, ObjectHandle, Parent, Resource},
+    orders::Order,
+    refinery::RECIPES,
+    shipyard::{BLUEPRINTS, Job, JobState},
+    space::{Galaxy, Mass, Time, World},
+    surface::Site,
+    table::{Column, Table},
+    trajectory::{Porkchop, TransferPlan},
+    ui::Hit,
 };
 use ratatui::{
     DefaultTerminal,
-    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::{Position, Rect},
 };
 
 /// Application.
@@ -16,28 +37,225 @@ pub struct App {
 
     /// game world
     pub world: Galaxy,
+    /// changes to the world's objects the view follows
+    changes: Subscription,
 
     /// currently inspected object and its parents
     pub view: Vec<ObjectHandle>,
     pub view_index: Option<usize>,
+    /// selected row of the inspected object's children
+    pub child_index: usize,
+    /// objects inspected before, most recent last, `None` for the known systems
+    back: Vec<Option<ObjectHandle>>,
+    /// objects left going back, to go forward to again
+    forward: Vec<Option<ObjectHandle>>,
+
+    /// what the main area shows
+    pub panel: Panel,
+    /// selected row of the fleet overview
+    pub fleet_index: usize,
+    /// selected row of the orders panel
+    pub order_index: usize,
+    /// selected blueprint of the shipyard panel
+    pub blueprint_index: usize,
+    /// selected refinery of the production panel
+    pub refinery_index: usize,
+    /// only list objects whose owner regards the player this way, unowned objects count as neutral
+    pub owner_filter: Option<Standing>,
+    /// selected resource of the market panel
+    pub resource_index: usize,
+    /// outcome of the last trade
+    pub trade: Option<Result<f64, TradeError>>,
+    /// selected flight and departure of the transfer planner
+    pub planner_cell: (usize, usize),
+
+    /// notifications received so far, oldest first
+    pub messages: Vec<Message>,
+    /// recent notifications shown over the panels, until they expire
+    pub toasts: Vec<(Message, Instant)>,
+    /// selected row of the message log, newest first
+    pub log_index: usize,
+    /// least severity the message log shows
+    pub log_filter: Severity,
+    /// pause the simulation on alerts
+    pub auto_pause: bool,
+    /// selected row of the bookmarks panel
+    pub bookmark_index: usize,
+    /// text being typed, if any
+    pub input: Option<Input>,
+    /// objects whose children the tree panel lists
+    pub expanded: HashSet<ObjectHandle>,
+    /// selected object of the tree panel, kept as it moves around the tree
+    pub tree_selected: Option<ObjectHandle>,
+    /// columns, filters and selected row of the table panel
+    pub table: Table,
+    /// objects marked for the comparison panel, in the order they were marked
+    pub compared: Vec<ObjectHandle>,
+    /// selected object of the comparison panel
+    pub compare_index: usize,
+
+    /// which keys trigger which actions
+    pub keymap: Keymap,
+    /// problems found loading the keymap, shown until the next key press
+    pub keymap_problems: Vec<String>,
+    /// whether the help overlay is shown
+    pub help: bool,
+    /// clickable regions of the last frame drawn
+    pub hits: RefCell<Vec<(Rect, Hit)>>,
+
+    /// simulated seconds per real second
+    pub warp: f64,
+    pub paused: bool,
+    /// simulation steps per real second, more of them running at high warp
+    pub step_rate: f64,
+    /// frames drawn per real second at most
+    pub frame_rate: f64,
+    /// simulated time due but not stepped through yet
+    backlog: f64,
+    /// when the last tick ran
+    last_tick: Instant,
+    /// whether anything changed since the last frame was drawn
+    dirty: bool,
 }
 
+/// fastest time warp available
+const MAX_WARP: f64 = 1_000_000.;
+
+/// what the main area of the screen shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Panel {
+    /// the currently inspected object
+    #[default]
+    View,
+    /// every ship owned by the player
+    Fleet,
+    /// order queue of the controlled ship
+    Orders,
+    /// blueprints and build queue of the inspected station
+    Shipyard,
+    /// refineries of the inspected structure
+    Production,
+    /// power budget of the inspected structure
+    Power,
+    /// prices at known stations, and trading where the controlled ship is
+    Market,
+    /// transfer windows from the controlled ship to the inspected object
+    Planner,
+    /// notifications received so far
+    Log,
+    /// places the player marked to get back to
+    Bookmarks,
+    /// the whole hierarchy of known objects
+    Tree,
+    /// known objects side by side, sorted and filtered
+    Table,
+    /// marked objects compared property by property
+    Compare,
+}
+
+/// text being typed, taking every key but those ending it
+#[derive(Clone, Debug)]
+pub enum Input {
+    /// new name of the selected bookmark
+    BookmarkName(String),
+    /// the table's name filter, edited in place so the rows follow along
+    TableFilter,
+}
+
+/// a line of the tree panel
+#[derive(Clone, Copy, Debug)]
+pub struct TreeRow {
+    pub object: ObjectHandle,
+    pub depth: usize,
+    /// known children, listed below when expanded
+    pub children: usize,
+    pub expanded: bool,
+}
+
+/// mass bought or sold with each key press
+const TRADE_LOT: Mass = Mass(100.);
+
+/// columns of departure times in the transfer planner
+pub const PLANNER_DEPARTURES: usize = 24;
+/// rows of flight durations in the transfer planner
+pub const PLANNER_FLIGHTS: usize = 10;
+
+/// messages kept in the log
+const MAX_MESSAGES: usize = 500;
+/// objects remembered to go back to
+const MAX_HISTORY: usize = 100;
+/// how long toasts stay on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+/// longest simulated time a single step may cover, so high warps run more steps rather than
+/// coarser ones
+const MAX_STEP: Time = Time(600.);
+
 impl Default for App {
     fn default() -> Self {
+        Self::with_frame_rate(TICK_FPS)
+    }
+}
+
+impl App {
+    // a fresh app with its event thread ticking at `frame_rate`, so that only one is ever spawned
+    fn with_frame_rate(frame_rate: f64) -> Self {
+        let mut world = Galaxy::new();
+        let changes = world.subscribe();
         Self {
             running: true,
-            events: EventHandler::new(),
-            world: Galaxy::new(),
+            events: EventHandler::with_frame_rate(frame_rate),
+            world,
+            changes,
             view: vec![],
             view_index: None,
+            child_index: 0,
+            back: vec![],
+            forward: vec![],
+            panel: Panel::default(),
+            fleet_index: 0,
+            order_index: 0,
+            blueprint_index: 0,
+            refinery_index: 0,
+            owner_filter: None,
+            resource_index: 0,
+            trade: None,
+            planner_cell: (0, 0),
+            messages: vec![],
+            toasts: vec![],
+            log_index: 0,
+            log_filter: Severity::default(),
+            auto_pause: true,
+            bookmark_index: 0,
+            input: None,
+            table: Table::default(),
+            compared: vec![],
+            compare_index: 0,
+            expanded: HashSet::new(),
+            tree_selected: None,
+            keymap: Keymap::default(),
+            keymap_problems: vec![],
+            help: false,
+            hits: RefCell::new(vec![]),
+            warp: 1.,
+            paused: false,
+            step_rate: TICK_FPS,
+            frame_rate,
+            backlog: 0.,
+            last_tick: Instant::now(),
+            dirty: true,
         }
     }
-}
 
-impl App {
     /// Constructs a new instance of [`App`].
     pub fn new() -> Self {
-        let mut app = App::default();
+        Self::with_rates(TICK_FPS, TICK_FPS)
+    }
+
+    /// Constructs a new instance of [`App`] drawing at `frame_rate` and stepping the simulation
+    /// `step_rate` times per second.
+    pub fn with_rates(frame_rate: f64, step_rate: f64) -> Self {
+        let mut app = App { step_rate, ..App::with_frame_rate(frame_rate) };
+        (app.keymap, app.keymap_problems) = Keymap::load();
 
         if let Some(handle) = app.world.get_handle() {
             app.view_goto(handle);
@@ -49,7 +267,9 @@ impl App {
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
         while self.running {
-            terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
+            if std::mem::take(&mut self.dirty) {
+                terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
+            }
             self.handle_events()?;
         }
         Ok(())
@@ -62,12 +282,22 @@ impl App {
                 crossterm::event::Event::Key(key_event)
                     if key_event.kind == crossterm::event::KeyEventKind::Press =>
                 {
+                    self.dirty = true;
                     self.handle_key_event(key_event)?
                 }
+                crossterm::event::Event::Mouse(mouse_event) if mouse_event.kind != MouseEventKind::Moved => {
+                    self.dirty = true;
+                    self.handle_mouse_event(mouse_event)?
+                }
+                crossterm::event::Event::Resize(..) => self.dirty = true,
                 _ => {}
             },
             Event::App(app_event) => match app_event {
                 AppEvent::Quit => self.quit(),
+                AppEvent::Notify(message) => {
+                    self.dirty = true;
+                    self.notify(message)
+                }
             },
         }
         Ok(())
@@ -75,14 +305,445 @@ impl App {
 
     /// Handles the key events and updates the state of [`App`].
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
-        match key_event.code {
-            KeyCode::Char('q') => self.events.send(AppEvent::Quit),
-            KeyCode::Char('c' | 'C') if key_event.modifiers == KeyModifiers::CONTROL => {
-                self.events.send(AppEvent::Quit)
+        self.keymap_problems.clear();
+        // typing takes every key but those bound to confirming or cancelling it
+        if self.input.is_some() {
+            match self.keymap.action(key_event.into(), &[Action::Select, Action::Back]) {
+                Some(Action::Select) => {
+                    if let Some(Input::BookmarkName(name)) = self.input.take()
+                        && let Some(bookmark) = self.world.bookmarks_mut().get_mut(self.bookmark_index)
+                        && !name.trim().is_empty()
+                    {
+                        bookmark.name = name.trim().to_string();
+                    }
+                }
+                // a cancelled filter lists everything again
+                Some(_) => {
+                    if let Some(Input::TableFilter) = self.input.take() {
+                        self.table.filter.clear();
+                    }
+                }
+                None => {
+                    let text = match self.input.as_mut().unwrap() {
+                        Input::BookmarkName(name) => name,
+                        Input::TableFilter => {
+                            self.table.index = 0;
+                            &mut self.table.filter
+                        }
+                    };
+                    match key_event.code {
+                        KeyCode::Char(c) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => text.push(c),
+                        KeyCode::Backspace => _ = text.pop(),
+                        _ => {}
+                    }
+                }
+            }
+            return Ok(());
+        }
+        match self.keymap.action(key_event.into(), &self.actions()) {
+            Some(action) => self.perform(action),
+            None => Ok(()),
+        }
+    }
+
+    /// Does what the action stands for in the current state of [`App`].
+    pub fn perform(&mut self, action: Action) -> color_eyre::Result<()> {
+        // the help overlay hides everything but quitting until it is closed
+        if self.help && action != Action::Quit {
+            self.help = !matches!(action, Action::Help | Action::Back);
+            return Ok(());
+        }
+        match action {
+            Action::Quit => self.events.send(AppEvent::Quit),
+            Action::Help => self.help = true,
+
+            // switch to the next ship of the fleet
+            Action::NextShip => {
+                let fleet = self.world.fleet();
+                let active = self.world.get_handle();
+                if let Some(index) = fleet.iter().position(|ship| Some(*ship) == active) {
+                    self.fleet_index = (index + 1) % fleet.len();
+                    self.order_index = 0;
+                    let next = fleet[self.fleet_index];
+                    self.world.set_handle(next);
+                    self.view_goto(next);
+                }
+            }
+            Action::Fleet => {
+                self.toggle_panel(Panel::Fleet);
+                let active = self.world.get_handle();
+                self.fleet_index = self.world.fleet().iter().position(|ship| Some(*ship) == active).unwrap_or(0);
+            }
+            Action::Shipyard => self.toggle_panel(Panel::Shipyard),
+            Action::Production => {
+                self.toggle_panel(Panel::Production);
+                self.refinery_index = 0;
+            }
+            Action::Power => self.toggle_panel(Panel::Power),
+            Action::OwnerFilter => {
+                self.owner_filter = match self.owner_filter {
+                    None => Some(Standing::Allied),
+                    Some(Standing::Allied) => Some(Standing::Neutral),
+                    Some(Standing::Neutral) => Some(Standing::Hostile),
+                    Some(Standing::Hostile) => None,
+                };
+                self.child_index = 0;
+            }
+            Action::Market => {
+                self.toggle_panel(Panel::Market);
+                self.trade = None;
+            }
+            Action::Planner => {
+                self.toggle_panel(Panel::Planner);
+                // start from the cheapest window
+                self.planner_cell = self.porkchop().and_then(|porkchop| porkchop.cheapest()).unwrap_or((0, 0));
+            }
+            Action::Log => {
+                self.toggle_panel(Panel::Log);
+                self.log_index = 0;
+            }
+            Action::Bookmarks => {
+                self.toggle_panel(Panel::Bookmarks);
+                self.bookmark_index = 0;
+            }
+            // open the tree where the view is
+            Action::Tree => {
+                self.toggle_panel(Panel::Tree);
+                if let Some((view, parents)) = self.view.split_last() {
+                    self.expanded.extend(parents);
+                    self.tree_selected = Some(*view);
+                }
+            }
+            Action::Table => {
+                self.toggle_panel(Panel::Table);
+                self.table.index = 0;
+            }
+            Action::Compare => {
+                self.toggle_panel(Panel::Compare);
+                self.compare_index = 0;
+            }
+            Action::Orders => {
+                self.toggle_panel(Panel::Orders);
+                self.order_index = 0;
+            }
+
+            // queue blueprints at the inspected station
+            Action::Up if self.panel == Panel::Shipyard => {
+                self.blueprint_index = self.blueprint_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Shipyard => {
+                self.blueprint_index = (self.blueprint_index + 1).min(BLUEPRINTS.len() - 1);
+            }
+            Action::Select if self.panel == Panel::Shipyard => {
+                let Some(station) = self.get_view().filter(|_| self.own_station()) else { return Ok(()) };
+                // components are fitted to the controlled ship when it is in the station's orbit
+                let target = self.world.get_handle().filter(|ship| {
+                    matches!(self.world.get_object(*ship).unwrap().parent, Parent::Relation(parent) if parent == station)
+                });
+                if let Some(shipyard) = self
+                    .world
+                    .get_object_mut(station)
+                    .and_then(|object| object.structure_mut())
+                    .and_then(|structure| structure.shipyards_mut().next())
+                {
+                    shipyard.queue.push_back(Job::new(self.blueprint_index, target));
+                }
+            }
+            // cancel the last job if it hasn't started
+            Action::CancelJob => {
+                if let Some(shipyard) = self
+                    .get_view()
+                    .filter(|_| self.own_station())
+                    .and_then(|station| self.world.get_object_mut(station))
+                    .and_then(|object| object.structure_mut())
+                    .and_then(|structure| structure.shipyards_mut().next())
+                    && shipyard.queue.back().is_some_and(|job| matches!(job.state, JobState::Waiting))
+                {
+                    shipyard.queue.pop_back();
+                }
+            }
+
+            // pick recipes for the inspected structure's refineries
+            Action::Up if self.panel == Panel::Production => {
+                self.refinery_index = self.refinery_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Production => {
+                let count = self
+                    .get_view()
+                    .and_then(|view| self.world.get_object(view))
+                    .and_then(|object| object.structure())
+                    .map_or(0, |structure| structure.count(ComponentKind::Refinery));
+                self.refinery_index = (self.refinery_index + 1).min(count.saturating_sub(1));
+            }
+            Action::Select if self.panel == Panel::Production => {
+                if let Some(refinery) = self
+                    .get_view()
+                    .and_then(|view| self.world.get_object_mut(view))
+                    .and_then(|object| object.structure_mut())
+                    .and_then(|structure| structure.refineries_mut().nth(self.refinery_index))
+                {
+                    // cycle through the recipes, then none
+                    let next = match refinery.recipe {
+                        None => Some(0),
+                        Some(recipe) if recipe + 1 < RECIPES.len() => Some(recipe + 1),
+                        Some(_) => None,
+                    };
+                    refinery.set_recipe(next);
+                }
+            }
+
+            // trade the selected resource at the station the controlled ship orbits
+            Action::Up if self.panel == Panel::Market => {
+                self.resource_index = self.resource_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Market => {
+                self.resource_index = (self.resource_index + 1).min(Resource::ALL.len() - 1);
+            }
+            Action::Buy => {
+                if let Some(ship) = self.world.get_handle() {
+                    self.trade = Some(self.world.buy(ship, Resource::ALL[self.resource_index], TRADE_LOT));
+                }
+            }
+            Action::Sell => {
+                if let Some(ship) = self.world.get_handle() {
+                    self.trade = Some(self.world.sell(ship, Resource::ALL[self.resource_index], TRADE_LOT));
+                }
+            }
+
+            // pick a window in the transfer planner
+            Action::Up if self.panel == Panel::Planner => {
+                self.planner_cell.0 = self.planner_cell.0.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Planner => {
+                self.planner_cell.0 = (self.planner_cell.0 + 1).min(PLANNER_FLIGHTS - 1);
+            }
+            Action::Left if self.panel == Panel::Planner => {
+                self.planner_cell.1 = self.planner_cell.1.saturating_sub(1);
+            }
+            Action::Right if self.panel == Panel::Planner => {
+                self.planner_cell.1 = (self.planner_cell.1 + 1).min(PLANNER_DEPARTURES - 1);
+            }
+            // fly the selected window
+            Action::Select if self.panel == Panel::Planner => {
+                let (flight, departure) = self.planner_cell;
+                if let (Some(target), Some(porkchop)) = (self.get_view(), self.porkchop())
+                    && let Some(delta_v) = porkchop.delta_v[flight][departure]
+                {
+                    let plan = TransferPlan { departure: porkchop.departures[departure], duration: porkchop.flights[flight], delta_v };
+                    self.insert_order(Order::Window(target, plan));
+                }
+            }
+
+            // browse the message log, and look at what a message is about
+            Action::Up if self.panel == Panel::Log => {
+                self.log_index = self.log_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Log => {
+                self.log_index = (self.log_index + 1).min(self.log().len().saturating_sub(1));
+            }
+            Action::Select if self.panel == Panel::Log => {
+                if let Some(subject) = self.log().get(self.log_index).map(|message| message.notification.subject())
+                    && self.world.get_object(subject).is_some()
+                {
+                    self.view_goto(subject);
+                    self.panel = Panel::View;
+                }
+            }
+            Action::LogFilter => {
+                self.log_filter = match self.log_filter {
+                    Severity::Info => Severity::Warning,
+                    Severity::Warning => Severity::Alert,
+                    Severity::Alert => Severity::Info,
+                };
+                self.log_index = 0;
+            }
+            Action::AutoPause => self.auto_pause = !self.auto_pause,
+
+            // browse the tree, expanding and collapsing objects
+            Action::Up | Action::Down if self.panel == Panel::Tree => {
+                let rows = self.tree_rows();
+                let index = self.tree_index(&rows);
+                let index = if action == Action::Up { index.saturating_sub(1) } else { (index + 1).min(rows.len().saturating_sub(1)) };
+                self.tree_selected = rows.get(index).map(|row| row.object);
+            }
+            Action::Right if self.panel == Panel::Tree => {
+                let rows = self.tree_rows();
+                let index = self.tree_index(&rows);
+                match rows.get(index) {
+                    Some(row) if row.expanded => self.tree_selected = rows.get(index + 1).map(|row| row.object),
+                    Some(row) if row.children > 0 => _ = self.expanded.insert(row.object),
+                    _ => {}
+                }
+            }
+            Action::Left if self.panel == Panel::Tree => {
+                let rows = self.tree_rows();
+                let index = self.tree_index(&rows);
+                match rows.get(index) {
+                    Some(row) if row.expanded => _ = self.expanded.remove(&row.object),
+                    // up to the parent, the closest row above that is less deep
+                    Some(row) => {
+                        if let Some(parent) = rows[..index].iter().rev().find(|parent| parent.depth < row.depth) {
+                            self.tree_selected = Some(parent.object);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Action::Select if self.panel == Panel::Tree => {
+                let rows = self.tree_rows();
+                if let Some(row) = rows.get(self.tree_index(&rows)) {
+                    self.view_goto(row.object);
+                    self.panel = Panel::View;
+                }
+            }
+
+            // sort and filter the table, and look at the objects it lists
+            Action::Up if self.panel == Panel::Table => {
+                self.table.index = self.table.index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Table => {
+                let len = self.table.rows(&self.world, self.get_view()).len();
+                self.table.index = (self.table.index + 1).min(len.saturating_sub(1));
+            }
+            Action::Left if self.panel == Panel::Table => {
+                self.table.column = self.table.column.saturating_sub(1);
+            }
+            Action::Right if self.panel == Panel::Table => {
+                self.table.column = (self.table.column + 1).min(Column::ALL.len() - 1);
+            }
+            Action::Select if self.panel == Panel::Table => {
+                if let Some(row) = self.table.rows(&self.world, self.get_view()).get(self.table.index) {
+                    self.view_goto(row.object);
+                    self.panel = Panel::View;
+                }
+            }
+            Action::TableSort => self.table.sort_selected(),
+            Action::TableKind => {
+                self.table.cycle_kind();
+                self.table.index = 0;
+            }
+            Action::TableScope => {
+                self.table.system = !self.table.system;
+                self.table.index = 0;
+            }
+            Action::TableSearch => self.input = Some(Input::TableFilter),
+
+            // pick objects to compare, and the one to look at
+            Action::Mark => {
+                if let Some(object) = self.mark_target() {
+                    match self.compared.iter().position(|marked| *marked == object) {
+                        Some(index) => _ = self.compared.remove(index),
+                        None => self.compared.push(object),
+                    }
+                    self.compare_index = self.compare_index.min(self.compared.len().saturating_sub(1));
+                }
+            }
+            Action::CompareClear => {
+                self.compared.clear();
+                self.compare_index = 0;
+            }
+            Action::Left if self.panel == Panel::Compare => {
+                self.compare_index = self.compare_index.saturating_sub(1);
+            }
+            Action::Right if self.panel == Panel::Compare => {
+                self.compare_index = (self.compare_index + 1).min(self.compared.len().saturating_sub(1));
+            }
+            Action::Select if self.panel == Panel::Compare => {
+                if let Some(&object) = self.compared.get(self.compare_index) {
+                    self.view_goto(object);
+                    self.panel = Panel::View;
+                }
+            }
+
+            // go back to bookmarked places, and keep them tidy
+            Action::Up if self.panel == Panel::Bookmarks => {
+                self.bookmark_index = self.bookmark_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Bookmarks => {
+                self.bookmark_index = (self.bookmark_index + 1).min(self.world.bookmarks().len().saturating_sub(1));
+            }
+            Action::Select if self.panel == Panel::Bookmarks => {
+                if let Some(bookmark) = self.world.bookmarks().get(self.bookmark_index).cloned() {
+                    self.view_goto(bookmark.object);
+                    if let Some(site) = bookmark.site.filter(|site| *site < self.view_sites().len()) {
+                        self.child_index = self.view_children().len() + site;
+                    }
+                    self.panel = Panel::View;
+                }
+            }
+            Action::BookmarkRename => {
+                self.input = self.world.bookmarks().get(self.bookmark_index).map(|bookmark| Input::BookmarkName(bookmark.name.clone()));
+            }
+            Action::BookmarkDelete => {
+                let bookmarks = self.world.bookmarks_mut();
+                if self.bookmark_index < bookmarks.len() {
+                    bookmarks.remove(self.bookmark_index);
+                }
+                self.bookmark_index = self.bookmark_index.min(bookmarks.len().saturating_sub(1));
+            }
+            Action::Bookmark => {
+                if let Some(view) = self.get_view() {
+                    self.world.toggle_bookmark(view, self.selected_site());
+                }
+            }
+
+            // time warp
+            Action::Pause => self.paused = !self.paused,
+            Action::WarpFaster => self.warp = (self.warp * 10.).min(MAX_WARP),
+            Action::WarpSlower => self.warp = (self.warp / 10.).max(1.),
+
+            // edit the controlled ship's orders, targeting the inspected object
+            Action::Up if self.panel == Panel::Orders => {
+                self.order_index = self.order_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Orders => {
+                let len = self.world.get_handle().and_then(|ship| self.world.get_orders(ship)).map_or(0, |queue| queue.orders.len());
+                self.order_index = (self.order_index + 1).min(len.saturating_sub(1));
+            }
+            Action::OrderTransfer => {
+                if let Some(target) = self.get_view() {
+                    self.insert_order(Order::Transfer(target));
+                }
+            }
+            Action::OrderUnload => {
+                if let Some(target) = self.get_view() {
+                    self.insert_order(Order::Unload(target));
+                }
+            }
+            Action::OrderLand => {
+                if let (Some(body), Some(site)) = (self.get_view(), self.selected_site()) {
+                    self.insert_order(Order::Land(body, site));
+                }
+            }
+            Action::OrderMine => self.insert_order(Order::Mine),
+            Action::OrderRepeat => self.insert_order(Order::Repeat),
+            Action::OrderDelete => {
+                if let Some(ship) = self.world.get_handle()
+                    && let Some(queue) = self.world.get_orders_mut(ship)
+                {
+                    queue.remove(self.order_index);
+                    self.order_index = self.order_index.min(queue.orders.len().saturating_sub(1));
+                }
+            }
+            Action::Up if self.panel == Panel::Fleet => {
+                self.fleet_index = self.fleet_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::Fleet => {
+                self.fleet_index = (self.fleet_index + 1).min(self.world.fleet().len().saturating_sub(1));
+            }
+            // take control of the selected ship
+            Action::Select if self.panel == Panel::Fleet => {
+                if let Some(&ship) = self.world.fleet().get(self.fleet_index) {
+                    self.world.set_handle(ship);
+                    self.order_index = 0;
+                    self.view_goto(ship);
+                    self.panel = Panel::View;
+                }
             }
 
-            KeyCode::Esc => {
-                if self.view_index == None {
+            Action::Back if self.panel != Panel::View => self.panel = Panel::View,
+            Action::Back => {
+                if self.view_index.is_none() {
                     if let Some(handle) = self.world.get_handle() {
                         self.view_goto(handle);
                     }
@@ -90,7 +751,7 @@ impl App {
                     self.view_index = None;
                 }
             }
-            KeyCode::Left => match &mut self.view_index {
+            Action::Left => match &mut self.view_index {
                 Some(index) => {
                     if *index > 0 {
                         *index -= 1
@@ -99,14 +760,14 @@ impl App {
                     }
                 }
                 None => {
-                    if self.view.len() != 0 {
+                    if !self.view.is_empty() {
                         self.view_index = Some(self.view.len() - 1);
                     } else {
                         self.view_index = Some(0)
                     }
                 }
             },
-            KeyCode::Right => match &mut self.view_index {
+            Action::Right => match &mut self.view_index {
                 Some(index) => {
                     if *index < self.view.len() {
                         *index += 1
@@ -118,8 +779,21 @@ impl App {
                     self.view_index = Some(0);
                 }
             },
+            // browse the inspected object's children
+            Action::Up if self.panel == Panel::View && self.view_index.is_none() => {
+                self.child_index = self.child_index.saturating_sub(1);
+            }
+            Action::Down if self.panel == Panel::View && self.view_index.is_none() => {
+                let len = self.view_children().len() + self.view_sites().len();
+                self.child_index = (self.child_index + 1).min(len.saturating_sub(1));
+            }
+            Action::Select if self.panel == Panel::View && self.view_index.is_none() => {
+                if let Some(&child) = self.view_children().get(self.child_index) {
+                    self.view_goto(child);
+                }
+            }
             // set view to selected
-            KeyCode::Enter => {
+            Action::Select => {
                 if let Some(index) = self.view_index {
                     if index == 0 {
                         self.view_reset();
@@ -129,11 +803,177 @@ impl App {
                 }
             }
             // reset view to handle
-            KeyCode::Backspace => {
+            Action::Home => {
                 if let Some(handle) = self.world.get_handle() {
                     self.view_goto(handle);
                 }
             }
+            Action::HistoryBack => self.view_travel(false),
+            Action::HistoryForward => self.view_travel(true),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Actions that do something in the current state, the only ones keys can trigger.
+    pub fn actions(&self) -> Vec<Action> {
+        if self.input.is_some() {
+            return vec![Action::Select, Action::Back];
+        }
+        let breadcrumb = self.view_index.is_some();
+        Action::ALL
+            .into_iter()
+            .filter(|action| match action {
+                Action::Up | Action::Down => match self.panel {
+                    Panel::View => !breadcrumb,
+                    Panel::Power | Panel::Compare => false,
+                    _ => true,
+                },
+                Action::Select => {
+                    breadcrumb
+                        || matches!(
+                            self.panel,
+                            Panel::View
+                                | Panel::Fleet
+                                | Panel::Production
+                                | Panel::Planner
+                                | Panel::Log
+                                | Panel::Bookmarks
+                                | Panel::Tree
+                                | Panel::Table
+                                | Panel::Compare
+                        )
+                        || self.panel == Panel::Shipyard && self.own_station()
+                }
+                Action::CancelJob => self.panel == Panel::Shipyard && self.own_station(),
+                Action::OwnerFilter => self.panel == Panel::View,
+                action => action.panel().is_none_or(|panel| panel == self.panel),
+            })
+            .collect()
+    }
+
+    /// Actions worth a hint in the footer for the current focus, with what they do there, help
+    /// first and then those of the panel.
+    pub fn hints(&self) -> Vec<(Action, &'static str)> {
+        let breadcrumb = self.view_index.is_some();
+        let mut actions = self.actions();
+        actions.sort_by_key(|action| (*action != Action::Help, action.panel().is_none()));
+        actions
+            .into_iter()
+            .filter_map(|action| {
+                let hint = match action {
+                    Action::Up | Action::Down => match self.panel {
+                        Panel::View => "browse",
+                        Panel::Fleet => "pick ship",
+                        Panel::Orders => "pick order",
+                        Panel::Shipyard => "pick blueprint",
+                        Panel::Production => "pick refinery",
+                        Panel::Market => "pick resource",
+                        Panel::Planner => "pick flight",
+                        Panel::Log => "pick message",
+                        Panel::Bookmarks => "pick bookmark",
+                        Panel::Tree | Panel::Table => "pick object",
+                        Panel::Power | Panel::Compare => return None,
+                    },
+                    Action::Left | Action::Right if self.panel == Panel::Planner => "pick departure",
+                    Action::Left | Action::Right if self.panel == Panel::Tree => "collapse/expand",
+                    Action::Left | Action::Right if self.panel == Panel::Table => "pick column",
+                    Action::Left | Action::Right if self.panel == Panel::Compare => "pick object",
+                    Action::Left | Action::Right => "breadcrumb",
+                    Action::Select if matches!(self.input, Some(Input::BookmarkName(_))) => "rename",
+                    Action::Back if matches!(self.input, Some(Input::BookmarkName(_))) => "cancel",
+                    Action::Select if self.input.is_some() => "done",
+                    Action::Back if self.input.is_some() => "clear",
+                    Action::Select if breadcrumb => "go there",
+                    Action::Select => match self.panel {
+                        Panel::Fleet => "take control",
+                        Panel::Shipyard => "build",
+                        Panel::Production => "change recipe",
+                        Panel::Planner => "fly window",
+                        Panel::Log => "go to subject",
+                        Panel::Bookmarks => "go there",
+                        Panel::Tree | Panel::Table | Panel::Compare => "inspect",
+                        _ => "enter",
+                    },
+                    Action::Back if breadcrumb => "cancel",
+                    Action::Back if self.panel != Panel::View => "close",
+                    Action::OwnerFilter => "filter owners",
+                    Action::CancelJob => "cancel last",
+                    Action::Buy => "buy 100kg",
+                    Action::Sell => "sell 100kg",
+                    Action::OrderTransfer => "transfer",
+                    Action::OrderLand => "land",
+                    Action::OrderMine => "mine",
+                    Action::OrderUnload => "unload",
+                    Action::OrderRepeat => "repeat",
+                    Action::OrderDelete => "delete",
+                    Action::LogFilter => "filter",
+                    Action::AutoPause => "auto-pause",
+                    Action::BookmarkRename => "rename",
+                    Action::TableSort => "sort",
+                    Action::TableKind => "kind",
+                    Action::TableScope => "system/galaxy",
+                    Action::TableSearch => "search",
+                    Action::BookmarkDelete => "delete",
+                    Action::Bookmark if self.panel == Panel::View => "bookmark",
+                    Action::Mark if matches!(self.panel, Panel::View | Panel::Tree | Panel::Table) => "mark",
+                    Action::Mark if self.panel == Panel::Compare => "unmark",
+                    Action::CompareClear => "unmark all",
+                    Action::Help => "help",
+                    _ => return None,
+                };
+                Some((action, hint))
+            })
+            .collect()
+    }
+
+    /// Handles clicks on the regions drawn last frame, and scrolls lists with the wheel.
+    pub fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> color_eyre::Result<()> {
+        // the bookmark being renamed stays selected
+        if self.input.is_some() {
+            return Ok(());
+        }
+        let hit = || {
+            let position = Position::new(mouse_event.column, mouse_event.row);
+            self.hits.borrow().iter().find(|(rect, _)| rect.contains(position)).map(|(_, hit)| *hit)
+        };
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => self.perform(Action::Up)?,
+            MouseEventKind::ScrollDown => self.perform(Action::Down)?,
+            MouseEventKind::Down(MouseButton::Left) => match hit() {
+                // select a breadcrumb segment, then enter it
+                Some(Hit::Crumb(index)) if self.view_index == Some(index) => self.perform(Action::Select)?,
+                Some(Hit::Crumb(index)) => self.view_index = Some(index),
+                // children are entered straight away, anything else is selected then activated
+                Some(Hit::Row(row)) if self.panel == Panel::View => match self.view_children().get(row) {
+                    Some(&child) => self.view_goto(child),
+                    None => self.child_index = row,
+                },
+                Some(Hit::Row(row)) if self.panel == Panel::Tree => {
+                    let object = self.tree_rows().get(row).map(|row| row.object);
+                    if object.is_some() && std::mem::replace(&mut self.tree_selected, object) == object {
+                        self.perform(Action::Select)?;
+                    }
+                }
+                Some(Hit::Row(row)) => {
+                    let selected = match self.panel {
+                        Panel::Fleet => &mut self.fleet_index,
+                        Panel::Orders => &mut self.order_index,
+                        Panel::Shipyard => &mut self.blueprint_index,
+                        Panel::Production => &mut self.refinery_index,
+                        Panel::Market => &mut self.resource_index,
+                        Panel::Log => &mut self.log_index,
+                        Panel::Bookmarks => &mut self.bookmark_index,
+                        Panel::Table => &mut self.table.index,
+                        _ => return Ok(()),
+                    };
+                    if std::mem::replace(selected, row) == row {
+                        self.perform(Action::Select)?;
+                    }
+                }
+                Some(Hit::Cell(flight, departure)) => self.planner_cell = (flight, departure),
+                None => {}
+            },
             _ => {}
         }
         Ok(())
@@ -141,31 +981,243 @@ impl App {
 
     /// Handles the tick event of the terminal.
     ///
-    /// The tick event is where you can update the state of your application with any logic that
-    /// needs to be updated at a fixed frame rate. E.g. polling a server, updating an animation.
-    pub fn tick(&self) {}
+    /// The simulation runs in fixed steps for the real time elapsed since the last tick, spending
+    /// at most half a frame on them so that input is never starved. Time it couldn't catch up on
+    /// is dropped past a second's worth, slowing the warp down rather than piling up.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(std::mem::replace(&mut self.last_tick, now));
+        let mut messages = vec![];
+        if self.paused {
+            self.backlog = 0.;
+        } else {
+            let step = (self.warp / self.step_rate).min(MAX_STEP.0);
+            let deadline = now + Duration::from_secs_f64(0.5 / self.frame_rate);
+            self.backlog = (self.backlog + elapsed.as_secs_f64() * self.warp).min(self.warp);
+            while self.backlog >= step && Instant::now() < deadline {
+                self.world.step(Time(step));
+                self.backlog -= step;
+                self.dirty = true;
+                // alerts stop the clock on the very step that raised them
+                let from = messages.len();
+                messages.extend(self.world.take_notifications());
+                if self.auto_pause && messages[from..].iter().any(|message| message.notification.severity() >= Severity::Alert) {
+                    self.paused = true;
+                    self.backlog = 0.;
+                    break;
+                }
+            }
+        }
+        messages.extend(self.world.take_notifications());
+        for message in messages {
+            self.events.send(AppEvent::Notify(message));
+        }
+        let toasts = self.toasts.len();
+        self.toasts.retain(|(_, expiry)| *expiry > now);
+        self.dirty |= self.toasts.len() != toasts;
+        // the inspected object may have been destroyed, or moved to another parent like a ship
+        // captured by a planet on its way
+        let changes = self.world.read_changes(self.changes);
+        let (mut despawned, mut moved) = (false, false);
+        for change in &changes {
+            match change {
+                Change::Despawned(object) => {
+                    despawned |= self.view.contains(object);
+                    self.compared.retain(|marked| marked != object);
+                }
+                Change::Reparented { object, .. } => moved |= self.view.contains(object),
+                Change::Spawned(_) | Change::MassChanged { .. } => {}
+            }
+        }
+        if despawned {
+            match self.world.get_handle() {
+                Some(ship) => self.view_goto(ship),
+                None => self.view_reset(),
+            }
+        } else if moved && let Some(&object_handle) = self.view.last() {
+            let ancestry = self.world.ancestry(object_handle);
+            self.view_index = self.view_index.filter(|index| *index < ancestry.len());
+            self.view = ancestry;
+        }
+    }
+
+    /// Logs a notification and shows it for a while, pausing on alerts if asked to.
+    pub fn notify(&mut self, message: Message) {
+        if self.auto_pause && message.notification.severity() >= Severity::Alert {
+            self.paused = true;
+        }
+        self.toasts.push((message.clone(), Instant::now() + TOAST_DURATION));
+        self.messages.push(message);
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+    }
+
+    /// messages the log shows, newest first
+    pub fn log(&self) -> Vec<&Message> {
+        self.messages.iter().rev().filter(|message| message.notification.severity() >= self.log_filter).collect()
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 
-    fn view_goto(&mut self, object_handle: ObjectHandle) {
-        let mut view = vec![object_handle];
+    fn toggle_panel(&mut self, panel: Panel) {
+        self.panel = if self.panel == panel { Panel::View } else { panel };
+    }
 
-        let mut current = &self.world.get_object(object_handle).unwrap().parent;
-        while let Parent::Relation(parent_handle) = current {
-            view.push(*parent_handle);
-            current = &self.world.get_object(*parent_handle).unwrap().parent
-        }
-        view.reverse();
-        self.view = view;
-        self.view_index = None;
+    // inserts after the selected order and selects the new one
+    fn insert_order(&mut self, order: Order) {
+        let Some(queue) = self.world.get_handle().and_then(|ship| self.world.get_orders_mut(ship)) else { return };
+        let index = if queue.orders.is_empty() { 0 } else { self.order_index + 1 };
+        queue.insert(index, order);
+        self.order_index = index.min(queue.orders.len() - 1);
+    }
+
+    fn view_goto(&mut self, object_handle: ObjectHandle) {
+        self.remember(Some(object_handle));
+        self.view_show(Some(object_handle));
     }
 
     fn view_reset(&mut self) {
-        self.view.clear();
+        self.remember(None);
+        self.view_show(None);
+    }
+
+    // inspects the object, or the known systems, leaving the history alone
+    fn view_show(&mut self, object_handle: Option<ObjectHandle>) {
+        self.view = object_handle.map_or(vec![], |object_handle| self.world.ancestry(object_handle));
         self.view_index = None;
+        self.child_index = 0;
+    }
+
+    // records the inspected object in the history when moving on to another one
+    fn remember(&mut self, next: Option<ObjectHandle>) {
+        let current = self.get_view();
+        if current != next {
+            self.back.push(current);
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+            self.forward.clear();
+        }
+    }
+
+    // steps back or forward through the history, skipping objects destroyed since
+    fn view_travel(&mut self, forward: bool) {
+        let current = self.get_view();
+        let (from, to) = if forward { (&mut self.forward, &mut self.back) } else { (&mut self.back, &mut self.forward) };
+        while let Some(entry) = from.pop() {
+            if entry != current && entry.is_none_or(|object| self.world.get_object(object).is_some()) {
+                to.push(current);
+                self.view_show(entry);
+                self.panel = Panel::View;
+                return;
+            }
+        }
+    }
+
+    /// detected children of the inspected object, closest first, or the known roots of the galaxy
+    pub fn view_children(&self) -> Vec<ObjectHandle> {
+        let mut children = self.unfiltered_children();
+        if let Some(standing) = self.owner_filter {
+            let owner = |child| self.world.get_object(child).and_then(|object| object.owner);
+            children.retain(|child| self.world.factions().standing(Some(Owner::Player), owner(*child)) == standing);
+        }
+        children
+    }
+
+    // known children of the view, or the known roots at the top
+    fn unfiltered_children(&self) -> Vec<ObjectHandle> {
+        self.known_children(self.get_view())
+    }
+
+    // known children of the object closest first, or the known roots by name
+    fn known_children(&self, object_handle: Option<ObjectHandle>) -> Vec<ObjectHandle> {
+        let knowledge = self.world.knowledge();
+        match object_handle.and_then(|object_handle| self.world.get_object(object_handle)) {
+            Some(object) => {
+                let mut children: Vec<_> = knowledge.known_children(object).collect();
+                children.sort_by(|a, b| {
+                    let distance = |child| object.get_child(child).unwrap().distance().0;
+                    distance(*a).total_cmp(&distance(*b))
+                });
+                children
+            }
+            None => {
+                let mut roots: Vec<_> = self
+                    .world
+                    .iter()
+                    .filter(|(handle, object)| matches!(object.parent, Parent::Position(_)) && knowledge.is_known(*handle))
+                    .map(|(handle, _)| handle)
+                    .collect();
+                roots.sort_by_key(|root| self.world.get_object(*root).unwrap().name);
+                roots
+            }
+        }
+    }
+
+    /// object the mark action is about: the selected one of the tree, table or comparison panel,
+    /// or the inspected one
+    pub fn mark_target(&self) -> Option<ObjectHandle> {
+        match self.panel {
+            Panel::Tree => {
+                let rows = self.tree_rows();
+                rows.get(self.tree_index(&rows)).map(|row| row.object)
+            }
+            Panel::Table => self.table.rows(&self.world, self.get_view()).get(self.table.index).map(|row| row.object),
+            Panel::Compare => self.compared.get(self.compare_index).copied(),
+            _ => self.get_view(),
+        }
+    }
+
+    /// lines of the tree panel, walking only into expanded objects so that the rest of the
+    /// galaxy costs nothing
+    pub fn tree_rows(&self) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        let mut stack: Vec<_> = self.known_children(None).into_iter().rev().map(|root| (root, 0)).collect();
+        while let Some((object, depth)) = stack.pop() {
+            let children = self.world.get_object(object).map_or(0, |object| self.world.knowledge().known_children(object).count());
+            let expanded = children > 0 && self.expanded.contains(&object);
+            rows.push(TreeRow { object, depth, children, expanded });
+            if expanded {
+                stack.extend(self.known_children(Some(object)).into_iter().rev().map(|child| (child, depth + 1)));
+            }
+        }
+        rows
+    }
+
+    /// row of the object selected in the tree, the first one if it isn't listed
+    pub fn tree_index(&self, rows: &[TreeRow]) -> usize {
+        rows.iter().position(|row| Some(row.object) == self.tree_selected).unwrap_or(0)
+    }
+
+    /// surface sites of the inspected body, once its composition is known
+    pub fn view_sites(&self) -> &[Site] {
+        match self.get_view() {
+            Some(view) if self.world.knowledge().get(view).is_some_and(|contact| contact.composition.is_some()) => {
+                self.world.sites(view)
+            }
+            _ => &[],
+        }
+    }
+
+    /// site selected in the list below the inspected object's children
+    pub fn selected_site(&self) -> Option<usize> {
+        let site = self.child_index.checked_sub(self.view_children().len())?;
+        (site < self.view_sites().len()).then_some(site)
+    }
+
+    /// makes `rect` clickable until the next frame is drawn
+    pub fn hit(&self, rect: Rect, hit: Hit) {
+        self.hits.borrow_mut().push((rect, hit));
+    }
+
+    /// transfer windows from the controlled ship to the inspected object
+    pub fn porkchop(&self) -> Option<Porkchop> {
+        let (ship, target) = (self.world.get_handle()?, self.get_view()?);
+        self.world.porkchop(ship, target, PLANNER_DEPARTURES, PLANNER_FLIGHTS)
     }
 
     pub fn get_view(&self) -> Option<ObjectHandle> {
@@ -175,4 +1227,9 @@ impl App {
     pub fn get_view_idx(&self) -> Option<ObjectHandle> {
         self.view.get(self.view_index?.checked_sub(1)?).cloned()
     }
+
+    /// whether the inspected station is the player's, as only those build for them
+    pub fn own_station(&self) -> bool {
+        self.get_view().and_then(|station| self.world.get_object(station)).is_some_and(|station| station.owner == Some(Owner::Player))
+    }
 }
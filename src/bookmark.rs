@@ -0,0 +1,36 @@
+use crate::{
+    object::ObjectHandle,
+    space::{Galaxy, World},
+};
+
+/// a place the player wants to get back to, kept with the world
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    pub name: String,
+    pub object: ObjectHandle,
+    /// surface site of the object, for mining spots
+    pub site: Option<usize>,
+}
+
+impl Galaxy {
+    /// bookmarks the object, or the site on its surface, or forgets it if it already was.
+    /// returns whether it is bookmarked now
+    pub fn toggle_bookmark(&mut self, object_handle: ObjectHandle, site: Option<usize>) -> bool {
+        let bookmarks = self.bookmarks_mut();
+        if let Some(index) = bookmarks.iter().position(|bookmark| bookmark.object == object_handle && bookmark.site == site) {
+            bookmarks.remove(index);
+            return false;
+        }
+        let Some(object) = self.get_object(object_handle) else { return false };
+        let name = match site.and_then(|site| self.sites(object_handle).get(site)) {
+            Some(site) => format!("{} {}", object.name, site.terrain),
+            None => object.name.to_string(),
+        };
+        self.bookmarks_mut().push(Bookmark { name, object: object_handle, site });
+        true
+    }
+
+    pub fn is_bookmarked(&self, object_handle: ObjectHandle, site: Option<usize>) -> bool {
+        self.bookmarks().iter().any(|bookmark| bookmark.object == object_handle && bookmark.site == site)
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::VecDeque;
+
+use crate::{object::ObjectHandle, space::Mass};
+
+/// a change to the objects of the galaxy
+#[derive(Clone, Debug)]
+pub enum Change {
+    /// the object was added
+    Spawned(ObjectHandle),
+    /// the object was removed, after everything under it
+    Despawned(ObjectHandle),
+    /// the object took a new relation, to a new parent or to the same one
+    Reparented { object: ObjectHandle, from: Option<ObjectHandle>, to: ObjectHandle },
+    /// the object got heavier or lighter
+    MassChanged { object: ObjectHandle, from: Mass, to: Mass },
+}
+
+/// a reader of the change log, seeing every change made after it subscribed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Subscription(usize);
+
+/// changes kept until every subscriber has read them
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    changes: VecDeque<Change>,
+    /// how many changes were read by everyone and dropped
+    dropped: usize,
+    /// how far each subscriber has read
+    cursors: Vec<usize>,
+}
+
+impl ChangeLog {
+    /// records a change, unless nobody is listening
+    pub fn push(&mut self, change: Change) {
+        if !self.cursors.is_empty() {
+            self.changes.push_back(change);
+        }
+    }
+
+    pub fn subscribe(&mut self) -> Subscription {
+        self.cursors.push(self.dropped + self.changes.len());
+        Subscription(self.cursors.len() - 1)
+    }
+
+    /// changes made since the subscriber last read, oldest first
+    pub fn read(&mut self, subscription: Subscription) -> Vec<Change> {
+        let end = self.dropped + self.changes.len();
+        let start = std::mem::replace(&mut self.cursors[subscription.0], end);
+        let changes = self.changes.range(start - self.dropped..).cloned().collect();
+
+        // forget what the slowest reader is done with
+        let done = self.cursors.iter().min().copied().unwrap_or(end) - self.dropped;
+        self.changes.drain(..done);
+        self.dropped += done;
+        changes
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt::Display;
+
+use crate::{
+    message::Notification,
+    orders::OrderState,
+    object::{
+        Composition, Field, FieldMorphology, ObjectBuilder, ObjectHandle, ObjectKind, Parent, ParentBuilder, Relation,
+    },
+    space::{Distance, Galaxy, Mass, World},
+};
+
+/// size of the debris cloud left by a destroyed object, in km
+const DEBRIS_RADIUS: Distance = Distance(100.);
+
+/// why an object can't take a relation to a parent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitError {
+    /// the orbit passes inside the parent
+    BelowSurface,
+    /// the orbit reaches past the parent's sphere of influence
+    OutsideSoi,
+    /// the orbit runs through one of the parent's belts
+    CrossesBelt,
+    /// only structures can be docked at or attached to
+    NotAStructure,
+    /// only bodies with a solid surface can be landed on
+    NoSurface,
+    /// the parent no longer exists
+    NoParent,
+}
+
+impl Display for OrbitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            OrbitError::BelowSurface => "orbit is below the surface",
+            OrbitError::OutsideSoi => "orbit is outside the sphere of influence",
+            OrbitError::CrossesBelt => "orbit crosses a belt",
+            OrbitError::NotAStructure => "target is not a structure",
+            OrbitError::NoSurface => "target has no surface to land on",
+            OrbitError::NoParent => "target no longer exists",
+        })
+    }
+}
+
+impl Galaxy {
+    /// radius within which the object's gravity dominates its parent's, `None` when unbounded.
+    /// objects resting on or at another share its sphere.
+    pub fn soi(&self, object_handle: ObjectHandle) -> Option<Distance> {
+        let object = self.get_object(object_handle)?;
+        let Parent::Relation(parent_handle) = object.parent else { return None };
+        match self.relation(object_handle)? {
+            Relation::Orbit(radius) => {
+                let parent = self.get_object(parent_handle)?;
+                Some(Distance(*radius as f64 * (object.mass.0 / parent.mass.0).powf(0.4)))
+            }
+            _ => self.soi(parent_handle),
+        }
+    }
+
+    /// checks that an object could take `relation` to the parent. structures are too light to
+    /// hold anything in orbit, so ships merely keep station next to them.
+    pub fn validate_orbit(&self, parent_handle: ObjectHandle, relation: &Relation) -> Result<(), OrbitError> {
+        let parent = self.get_object(parent_handle).ok_or(OrbitError::NoParent)?;
+        match relation {
+            Relation::Orbit(radius) => {
+                let radius = *radius as f64;
+                if parent.radius().is_some_and(|surface| radius <= surface.0) {
+                    return Err(OrbitError::BelowSurface);
+                }
+                if parent.structure().is_none() && self.soi(parent_handle).is_some_and(|soi| radius >= soi.0) {
+                    return Err(OrbitError::OutsideSoi);
+                }
+                let belts = parent.children.iter().flatten().filter_map(|(child, _)| match &self.get_object(*child)?.kind {
+                    ObjectKind::Field(Field { morphology: FieldMorphology::Belt { inner, outer }, .. }) => Some((*inner, *outer)),
+                    _ => None,
+                });
+                for (inner, outer) in belts {
+                    if inner.0 < radius && radius < outer.0 {
+                        return Err(OrbitError::CrossesBelt);
+                    }
+                }
+                Ok(())
+            }
+            Relation::Docked | Relation::Attached if parent.structure().is_none() => Err(OrbitError::NotAStructure),
+            Relation::Landed { .. } if self.sites(parent_handle).is_empty() => Err(OrbitError::NoSurface),
+            _ => Ok(()),
+        }
+    }
+
+    /// destroys objects orbiting inside their parent, and objects caught in the lane of a heavier
+    /// body sharing their parent, which leave a debris cloud behind
+    pub(crate) fn run_collisions(&mut self) {
+        let mut wrecks = vec![];
+        for (handle, object) in self.iter() {
+            let (Some(Relation::Orbit(radius)), Parent::Relation(parent_handle)) = (self.relation(handle), &object.parent)
+            else {
+                continue;
+            };
+            // fields are too diffuse to collide with anything, and ships in transit aren't on a
+            // circular orbit but passing through
+            if matches!(object.kind, ObjectKind::Field(_))
+                || self.get_orders(handle).is_some_and(|queue| matches!(queue.state, OrderState::Transit { .. }))
+            {
+                continue;
+            }
+            let parent = self.get_object(*parent_handle).unwrap();
+            if parent.radius().is_some_and(|surface| *radius as f64 <= surface.0) {
+                wrecks.push((handle, false));
+                continue;
+            }
+            let swept = parent.children.iter().flatten().any(|(sibling_handle, relation)| {
+                let sibling = self.get_object(*sibling_handle).unwrap();
+                match (relation, sibling.radius()) {
+                    (Relation::Orbit(lane), Some(size)) => {
+                        *sibling_handle != handle
+                            && sibling.mass.0 > object.mass.0
+                            && (*lane as f64 - *radius as f64).abs() < size.0
+                    }
+                    _ => false,
+                }
+            });
+            if swept {
+                wrecks.push((handle, true));
+            }
+        }
+
+        for (handle, debris) in wrecks {
+            self.wreck(handle, debris);
+        }
+    }
+
+    // destroys the object, leaving a cloud of its mass in its orbit when `debris` is set
+    fn wreck(&mut self, object_handle: ObjectHandle, debris: bool) {
+        let Some(object) = self.get_object(object_handle) else { return };
+        let (Parent::Relation(parent_handle), Some(relation)) = (object.parent.clone(), self.relation(object_handle).cloned())
+        else {
+            return;
+        };
+        let name = object.name;
+        let (mass, composition) = match object.composition() {
+            Some(composition) => (object.mass, composition.clone()),
+            // a hull of rock and metals, with the raw resources it carried on top of its mass
+            None => {
+                let hull = Composition::new(0., 0., 0.3, 0., 0.7).unwrap();
+                let cargo = object.structure().map_or(vec![], |structure| {
+                    Composition::ELEMENTS.map(|element| (element, structure.stored(element))).to_vec()
+                });
+                let carried = Mass(cargo.iter().map(|(_, mass)| mass.0).sum());
+                let composition = Composition::from_masses(&cargo)
+                    .and_then(|ore| hull.mix(object.mass, &ore, carried))
+                    .unwrap_or(hull);
+                (Mass(object.mass.0 + carried.0), composition)
+            }
+        };
+        let known = self.knowledge().is_known(object_handle);
+        if known {
+            self.notify(Notification::Wrecked { parent: parent_handle, name });
+        }
+
+        self.destroy(object_handle);
+        if debris && mass.0 > 0. {
+            let spawned = self.spawn_object(
+                ObjectBuilder::default().name("Debris").mass(Mass(mass.0)).kind(ObjectKind::Field(Field {
+                    composition,
+                    morphology: FieldMorphology::Cloud { radius: DEBRIS_RADIUS },
+                })),
+                ParentBuilder::Relation(parent_handle, relation),
+            );
+            if let Err(error) = spawned
+                && known
+            {
+                self.notify(Notification::Scattered { parent: parent_handle, name, error });
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::f64::consts::PI;
+
+use crate::{
+    object::{Object, ObjectHandle},
+    space::{Galaxy, Mass, Time, World},
+    surface::{G, fuel_for},
+    table::{Column, TableRow},
+    trajectory::TransferPlan,
+};
+
+/// which end of a property's range is the better one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Better {
+    Higher,
+    Lower,
+    Neither,
+}
+
+/// a property of the compared objects, with the value of each
+#[derive(Clone, Debug)]
+pub struct Property {
+    pub name: String,
+    /// values as shown, `-` when unknown
+    pub cells: Vec<String>,
+    /// values to rank them by, for numeric properties
+    pub values: Vec<Option<f64>>,
+    pub better: Better,
+}
+
+impl Property {
+    fn new(name: &str, better: Better, values: impl IntoIterator<Item = (String, Option<f64>)>) -> Self {
+        let (cells, values) = values.into_iter().unzip();
+        Property { name: name.to_string(), cells, values, better }
+    }
+
+    // a numeric property, shown with `format` when known
+    fn numeric(name: &str, better: Better, values: Vec<Option<f64>>, format: impl Fn(f64) -> String) -> Self {
+        let cells = values.iter().map(|value| value.map_or("-".to_string(), &format));
+        Property::new(name, better, cells.zip(values.iter().copied()))
+    }
+
+    /// whether the objects don't all share the same value
+    pub fn differs(&self) -> bool {
+        self.cells.iter().any(|cell| *cell != self.cells[0])
+    }
+
+    /// the object with the best value, if it is better than another one's
+    pub fn best(&self) -> Option<usize> {
+        let known = self.values.iter().enumerate().filter_map(|(idx, value)| Some((idx, (*value)?)));
+        let (best, value) = match self.better {
+            Better::Higher => known.max_by(|(_, a), (_, b)| a.total_cmp(b))?,
+            Better::Lower => known.min_by(|(_, a), (_, b)| a.total_cmp(b))?,
+            Better::Neither => return None,
+        };
+        let beaten = self.values.iter().flatten().any(|other| *other != value);
+        beaten.then_some(best)
+    }
+}
+
+impl Galaxy {
+    /// what the player knows of the objects side by side, from what they are to how costly
+    /// reaching them is for the controlled ship
+    pub fn compare(&self, objects: &[ObjectHandle]) -> Vec<Property> {
+        let rows: Vec<Option<TableRow>> = objects.iter().map(|object| TableRow::new(self, *object)).collect();
+        let cell = |column| rows.iter().map(move |row| row.as_ref().map_or("-".to_string(), |row| row.cell(column, self)));
+        let value = |column| rows.iter().map(|row| row.as_ref()?.value(column)).collect::<Vec<_>>();
+        let mut properties = vec![
+            Property::new("kind", Better::Neither, cell(Column::Kind).map(|cell| (cell, None))),
+            Property::new("owner", Better::Neither, cell(Column::Owner).map(|cell| (cell, None))),
+            Property::new("mass", Better::Higher, cell(Column::Mass).zip(value(Column::Mass))),
+            Property::new("radius", Better::Neither, cell(Column::Radius).zip(value(Column::Radius))),
+        ];
+        for column in Column::ALL {
+            if let Column::Share(resource) = column {
+                let share = |share: f64| format!("{:.0}%", share * 100.);
+                properties.push(Property::numeric(&resource.to_string(), Better::Higher, value(column), share));
+            }
+        }
+
+        // physical properties of bodies whose composition was measured
+        let bodies: Vec<_> = objects
+            .iter()
+            .zip(&rows)
+            .map(|(object, row)| row.as_ref()?.composition.as_ref().and(self.get_object(*object)))
+            .collect();
+        let derived = |property: fn(&Object) -> Option<f64>| {
+            bodies.iter().map(|body| body.and_then(property)).collect::<Vec<_>>()
+        };
+        properties.extend([
+            Property::numeric("gravity", Better::Lower, derived(|body| body.surface_gravity_g()), |g| format!("{g:.2}g")),
+            Property::numeric("escape velocity", Better::Lower, derived(escape_velocity), |v| format!("{v:.2}km/s")),
+            Property::numeric("density", Better::Neither, derived(density), |rho| format!("{rho:.0}kg/m³")),
+            Property::numeric("landing Δv", Better::Lower, derived(|body| body.landing_delta_v()), |dv| format!("{dv:.2}km/s")),
+        ]);
+
+        // getting there from the controlled ship
+        let ship = self.get_handle();
+        let plans: Vec<_> = objects.iter().map(|object| self.hohmann(ship?, *object)).collect();
+        let plan = |field: fn(&TransferPlan) -> f64| plans.iter().map(|plan| plan.as_ref().map(field)).collect::<Vec<_>>();
+        let ship_mass = ship.and_then(|ship| self.get_object(ship)).map_or(Mass(0.), |ship| ship.loaded_mass());
+        let now = self.get_time();
+        properties.extend([
+            Property::numeric("distance", Better::Lower, value(Column::Distance), |distance| format!("{distance:.0}km")),
+            Property::numeric("transfer Δv", Better::Lower, plan(|plan| plan.delta_v), |dv| format!("{dv:.2}km/s")),
+            Property::numeric("transfer fuel", Better::Lower, plan(|plan| plan.delta_v), |dv| fuel_for(ship_mass, dv).to_string()),
+            Property::numeric("flight time", Better::Lower, plan(|plan| plan.duration.0), |time| Time(time).to_string()),
+            Property::numeric("next window", Better::Lower, plan(|plan| plan.departure.0), |departure| {
+                format!("in {}", Time(departure - now.0))
+            }),
+        ]);
+        properties
+    }
+}
+
+// speed needed to leave the body's surface for good, in km/s
+fn escape_velocity(body: &Object) -> Option<f64> {
+    Some((2. * G * body.mass.0 / body.radius()?.0).sqrt())
+}
+
+// mean density of the body, in kg/m³
+fn density(body: &Object) -> Option<f64> {
+    let radius = body.radius()?.0 * 1000.;
+    Some(body.mass.0 / (4. / 3. * PI * radius.powi(3)))
+}
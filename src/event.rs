@@ -1,3 +1,4 @@
+use crate::message::Message;
 use color_eyre::eyre::WrapErr;
 use ratatui::crossterm::event::{self, Event as CrosstermEvent};
 use std::{
@@ -6,8 +7,8 @@ use std::{
     time::{Duration, Instant},
 };
 
-/// The frequency at which tick events are emitted.
-const TICK_FPS: f64 = 30.0;
+/// The default frequency at which tick events are emitted, and frames drawn.
+pub const TICK_FPS: f64 = 30.0;
 
 /// Representation of all possible events.
 #[derive(Clone, Debug)]
@@ -35,6 +36,8 @@ pub enum Event {
 pub enum AppEvent {
     /// Quit the application.
     Quit,
+    /// Something happened in the simulation that the player should hear about.
+    Notify(Message),
 }
 
 /// Terminal event handler.
@@ -46,11 +49,22 @@ pub struct EventHandler {
     receiver: mpsc::Receiver<Event>,
 }
 
+impl Default for EventHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EventHandler {
     /// Constructs a new instance of [`EventHandler`] and spawns a new thread to handle events.
     pub fn new() -> Self {
+        Self::with_frame_rate(TICK_FPS)
+    }
+
+    /// Constructs a new instance of [`EventHandler`] emitting `frame_rate` tick events per second.
+    pub fn with_frame_rate(frame_rate: f64) -> Self {
         let (sender, receiver) = mpsc::channel();
-        let actor = EventThread::new(sender.clone());
+        let actor = EventThread::new(sender.clone(), frame_rate);
         thread::spawn(|| actor.run());
         Self { sender, receiver }
     }
@@ -83,19 +97,21 @@ impl EventHandler {
 struct EventThread {
     /// Event sender channel.
     sender: mpsc::Sender<Event>,
+    /// Tick events emitted per second.
+    frame_rate: f64,
 }
 
 impl EventThread {
     /// Constructs a new instance of [`EventThread`].
-    fn new(sender: mpsc::Sender<Event>) -> Self {
-        Self { sender }
+    fn new(sender: mpsc::Sender<Event>, frame_rate: f64) -> Self {
+        Self { sender, frame_rate }
     }
 
     /// Runs the event thread.
     ///
     /// This function emits tick events at a fixed rate and polls for crossterm events in between.
     fn run(self) -> color_eyre::Result<()> {
-        let tick_interval = Duration::from_secs_f64(1.0 / TICK_FPS);
+        let tick_interval = Duration::from_secs_f64(1.0 / self.frame_rate);
         let mut last_tick = Instant::now();
         loop {
             // emit tick events at a fixed rate
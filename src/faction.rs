@@ -0,0 +1,295 @@
+use std::{collections::HashMap, fmt::Display};
+
+use slotmap::SecondaryMap;
+
+use crate::{
+    object::{ObjectHandle, Parent, Relation, Resource},
+    orders::{Order, OrderState},
+    rng::Rng,
+    space::{Galaxy, Mass, Time, World},
+};
+
+/// how long an AI ship waits before reconsidering once its orders are done or failed
+const DECISION_INTERVAL: Time = Time(3_600.);
+/// fuel AI ships top up to whenever they are at a market, in kg
+const FUEL_RESERVE: f64 = 300.;
+/// smallest load a hauler bothers trading, in kg
+const MIN_LOT: f64 = 100.;
+
+/// index into [`Factions::iter`]
+pub type FactionId = usize;
+
+/// an organisation owning stations and ships
+#[derive(Clone, Debug)]
+pub struct Faction {
+    pub name: &'static str,
+    pub credits: f64,
+}
+
+/// who an object belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Owner {
+    Player,
+    Faction(FactionId),
+}
+
+/// how two owners regard each other
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Standing {
+    Allied,
+    Neutral,
+    /// refuse to trade with each other
+    Hostile,
+}
+
+/// what an AI ship does with its time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// mines the nearest fields and bodies, unloading at home
+    Miner,
+    /// buys resources where they are cheap and sells them where they are dear
+    Hauler,
+    /// flies between the faction's stations
+    Patrol,
+}
+
+/// AI in control of a faction's ship
+#[derive(Clone, Debug)]
+pub struct Pilot {
+    pub faction: FactionId,
+    pub role: Role,
+    /// station the ship belongs to, where miners unload
+    pub home: ObjectHandle,
+    /// the pilot doesn't reconsider before this time
+    next_decision: Time,
+}
+
+/// every faction and the pilots flying their ships. decisions draw from a generator seeded by the
+/// world, so that a seed always plays out the same way.
+#[derive(Debug)]
+pub struct Factions {
+    factions: Vec<Faction>,
+    pilots: SecondaryMap<ObjectHandle, Pilot>,
+    /// standings between owners, keyed by the ordered pair, neutral when missing
+    standings: HashMap<(Owner, Owner), Standing>,
+    rng: Rng,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Role::Miner => "miner",
+            Role::Hauler => "hauler",
+            Role::Patrol => "patrol",
+        })
+    }
+}
+
+impl Display for Standing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Standing::Allied => "allied",
+            Standing::Neutral => "neutral",
+            Standing::Hostile => "hostile",
+        })
+    }
+}
+
+impl Factions {
+    pub fn new(seed: u64) -> Self {
+        Self { factions: vec![], pilots: SecondaryMap::new(), standings: HashMap::new(), rng: Rng::new(seed) }
+    }
+
+    pub fn add(&mut self, name: &'static str, credits: f64) -> FactionId {
+        self.factions.push(Faction { name, credits });
+        self.factions.len() - 1
+    }
+
+    pub fn get(&self, faction: FactionId) -> Option<&Faction> {
+        self.factions.get(faction)
+    }
+
+    pub fn get_mut(&mut self, faction: FactionId) -> Option<&mut Faction> {
+        self.factions.get_mut(faction)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Faction> {
+        self.factions.iter()
+    }
+
+    /// the faction's name, or "you" for the player
+    pub fn name(&self, owner: Owner) -> &'static str {
+        match owner {
+            Owner::Player => "you",
+            Owner::Faction(faction) => self.factions.get(faction).map_or("unknown", |faction| faction.name),
+        }
+    }
+
+    /// how two owners regard each other. owners are allied with themselves, and everyone is
+    /// neutral towards unowned objects.
+    pub fn standing(&self, a: Option<Owner>, b: Option<Owner>) -> Standing {
+        match (a, b) {
+            (Some(a), Some(b)) if a == b => Standing::Allied,
+            (Some(a), Some(b)) => self.standings.get(&(a.min(b), a.max(b))).copied().unwrap_or(Standing::Neutral),
+            _ => Standing::Neutral,
+        }
+    }
+
+    pub fn set_standing(&mut self, a: Owner, b: Owner, standing: Standing) {
+        self.standings.insert((a.min(b), a.max(b)), standing);
+    }
+
+    /// puts a faction's AI in control of the ship
+    pub fn assign(&mut self, ship: ObjectHandle, faction: FactionId, role: Role, home: ObjectHandle) {
+        self.pilots.insert(ship, Pilot { faction, role, home, next_decision: Time(0.) });
+    }
+
+    /// takes the AI off the ship
+    pub fn dismiss(&mut self, ship: ObjectHandle) {
+        self.pilots.remove(ship);
+    }
+
+    pub fn pilot(&self, ship: ObjectHandle) -> Option<&Pilot> {
+        self.pilots.get(ship)
+    }
+
+    /// every AI-controlled ship
+    pub fn pilots(&self) -> impl Iterator<Item = (ObjectHandle, &Pilot)> {
+        self.pilots.iter()
+    }
+}
+
+impl Galaxy {
+    /// how the owners of two objects regard each other
+    pub fn standing(&self, a: ObjectHandle, b: ObjectHandle) -> Standing {
+        let owner = |handle| self.get_object(handle).and_then(|object| object.owner);
+        self.factions().standing(owner(a), owner(b))
+    }
+
+    /// gives new orders to the AI ships that are done with theirs
+    pub(crate) fn run_ai(&mut self) {
+        let ships: Vec<ObjectHandle> = self.factions().pilots().map(|(ship, _)| ship).collect();
+        for ship in ships {
+            let pilot = self.factions().pilot(ship).unwrap().clone();
+            let busy = self
+                .get_orders(ship)
+                .is_some_and(|queue| !queue.is_done() && !matches!(queue.state, OrderState::Failed(_)));
+            if busy || pilot.next_decision.0 > self.get_time().0 {
+                continue;
+            }
+
+            self.refuel(ship);
+            let orders = match pilot.role {
+                Role::Miner => self.plan_mining(ship, pilot.home),
+                Role::Hauler => self.plan_trade(ship),
+                Role::Patrol => self.plan_patrol(ship, pilot.faction),
+            };
+            if let Some(queue) = self.get_orders_mut(ship) {
+                queue.clear();
+                queue.orders = orders;
+            }
+            self.factions_mut().pilots[ship].next_decision = Time(self.get_time().0 + DECISION_INTERVAL.0);
+        }
+    }
+
+    // tops up the ship's fuel at the market it orbits
+    fn refuel(&mut self, ship: ObjectHandle) {
+        let fuel = self.get_object(ship).unwrap().structure().map_or(0., |structure| structure.stored(Resource::Fuel).0);
+        if fuel < FUEL_RESERVE {
+            let _ = self.buy(ship, Resource::Fuel, Mass(FUEL_RESERVE - fuel));
+        }
+    }
+
+    // brings any ore home, otherwise mines one of the sites closest to home
+    fn plan_mining(&mut self, ship: ObjectHandle, home: ObjectHandle) -> Vec<Order> {
+        let structure = self.get_object(ship).unwrap().structure();
+        let ore = Resource::ALL
+            .iter()
+            .filter(|resource| **resource != Resource::Fuel)
+            .any(|resource| structure.is_some_and(|structure| structure.stored(*resource).0 > 0.));
+        if ore {
+            return vec![Order::Unload(home)];
+        }
+
+        let sites: Vec<(ObjectHandle, f64)> = self
+            .iter()
+            .filter(|(_, object)| object.composition().is_some() && matches!(object.parent, Parent::Relation(_)))
+            .filter_map(|(handle, _)| Some((handle, self.distance(home, handle)?.0)))
+            .collect();
+        let Some(nearest) = sites.iter().map(|(_, distance)| *distance).min_by(f64::total_cmp) else {
+            return vec![];
+        };
+        let close: Vec<ObjectHandle> =
+            sites.iter().filter(|(_, distance)| *distance <= nearest * 2.).map(|(handle, _)| *handle).collect();
+        let site = *self.factions_mut().rng.choose(&close).unwrap();
+        vec![Order::Transfer(site), Order::Mine, Order::Unload(home)]
+    }
+
+    // sells what the ship carries at the market it orbits and buys the cargo with the best margin
+    // at another market, otherwise heads for a random market
+    fn plan_trade(&mut self, ship: ObjectHandle) -> Vec<Order> {
+        let markets: Vec<ObjectHandle> = self
+            .iter()
+            .filter(|(_, object)| object.structure().is_some_and(|structure| structure.market().is_some()))
+            .map(|(handle, _)| handle)
+            .filter(|market| self.standing(ship, *market) != Standing::Hostile)
+            .collect();
+        let Ok(here) = self.trading_station(ship) else {
+            return self.factions_mut().rng.choose(&markets).map_or(vec![], |market| vec![Order::Transfer(*market)]);
+        };
+
+        for resource in Resource::ALL {
+            let reserve = if resource == Resource::Fuel { FUEL_RESERVE } else { 0. };
+            let carried = self.get_object(ship).unwrap().structure().map_or(0., |s| s.stored(resource).0) - reserve;
+            if carried > 0. {
+                let _ = self.sell(ship, resource, Mass(carried));
+            }
+        }
+
+        let credits = self.factions().pilot(ship).and_then(|pilot| self.factions().get(pilot.faction)).unwrap().credits;
+        let free = self.get_object(ship).unwrap().structure().map_or(0., |structure| structure.free().0);
+        let station = self.get_object(here).unwrap().structure().unwrap();
+        let market = station.market().unwrap();
+        let mut best: Option<(f64, Resource, f64, ObjectHandle)> = None;
+        for resource in Resource::ALL {
+            let ask = market.ask(station, resource);
+            let lot = free.min(station.stored(resource).0 / 2.).min(credits / ask).floor();
+            if lot < MIN_LOT {
+                continue;
+            }
+            for &other in markets.iter().filter(|other| **other != here) {
+                let structure = self.get_object(other).unwrap().structure().unwrap();
+                let profit = (structure.market().unwrap().bid(structure, resource) - ask) * lot;
+                if profit > 0. && best.is_none_or(|(best, ..)| profit > best) {
+                    best = Some((profit, resource, lot, other));
+                }
+            }
+        }
+
+        match best {
+            Some((_, resource, lot, destination)) if self.buy(ship, resource, Mass(lot)).is_ok() => {
+                vec![Order::Transfer(destination)]
+            }
+            _ => {
+                let others: Vec<ObjectHandle> = markets.into_iter().filter(|market| *market != here).collect();
+                self.factions_mut().rng.choose(&others).map_or(vec![], |market| vec![Order::Transfer(*market)])
+            }
+        }
+    }
+
+    // flies to another of the faction's stations
+    fn plan_patrol(&mut self, ship: ObjectHandle, faction: FactionId) -> Vec<Order> {
+        let parent = match self.get_object(ship).unwrap().parent {
+            Parent::Relation(parent) => Some(parent),
+            Parent::Position(_) => None,
+        };
+        let stations: Vec<ObjectHandle> = self
+            .iter()
+            .filter(|(_, object)| object.owner == Some(Owner::Faction(faction)) && object.structure().is_some())
+            .map(|(handle, _)| handle)
+            .filter(|station| !matches!(self.relation(*station), Some(Relation::Attached)))
+            .filter(|station| Some(*station) != parent && self.factions().pilot(*station).is_none())
+            .collect();
+        self.factions_mut().rng.choose(&stations).map_or(vec![], |station| vec![Order::Transfer(*station)])
+    }
+}
@@ -0,0 +1,538 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::Panel;
+
+/// something the player can do, whatever key triggers it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    NextShip,
+    Fleet,
+    Orders,
+    Shipyard,
+    Production,
+    Power,
+    Market,
+    Planner,
+    Log,
+    Bookmarks,
+    Tree,
+    Table,
+    Compare,
+    OwnerFilter,
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+    Home,
+    HistoryBack,
+    HistoryForward,
+    Bookmark,
+    Mark,
+    Pause,
+    WarpFaster,
+    WarpSlower,
+    CancelJob,
+    Buy,
+    Sell,
+    OrderTransfer,
+    OrderLand,
+    OrderMine,
+    OrderUnload,
+    OrderRepeat,
+    OrderDelete,
+    LogFilter,
+    AutoPause,
+    BookmarkRename,
+    BookmarkDelete,
+    TableSort,
+    TableKind,
+    TableScope,
+    TableSearch,
+    CompareClear,
+}
+
+impl Action {
+    pub const ALL: [Action; 48] = [
+        Action::Quit,
+        Action::Help,
+        Action::NextShip,
+        Action::Fleet,
+        Action::Orders,
+        Action::Shipyard,
+        Action::Production,
+        Action::Power,
+        Action::Market,
+        Action::Planner,
+        Action::Log,
+        Action::Bookmarks,
+        Action::Tree,
+        Action::Table,
+        Action::Compare,
+        Action::OwnerFilter,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Select,
+        Action::Back,
+        Action::Home,
+        Action::HistoryBack,
+        Action::HistoryForward,
+        Action::Bookmark,
+        Action::Mark,
+        Action::Pause,
+        Action::WarpFaster,
+        Action::WarpSlower,
+        Action::CancelJob,
+        Action::Buy,
+        Action::Sell,
+        Action::OrderTransfer,
+        Action::OrderLand,
+        Action::OrderMine,
+        Action::OrderUnload,
+        Action::OrderRepeat,
+        Action::OrderDelete,
+        Action::LogFilter,
+        Action::AutoPause,
+        Action::BookmarkRename,
+        Action::BookmarkDelete,
+        Action::TableSort,
+        Action::TableKind,
+        Action::TableScope,
+        Action::TableSearch,
+        Action::CompareClear,
+    ];
+
+    /// name of the action in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::NextShip => "next-ship",
+            Action::Fleet => "fleet",
+            Action::Orders => "orders",
+            Action::Shipyard => "shipyard",
+            Action::Production => "production",
+            Action::Power => "power",
+            Action::Market => "market",
+            Action::Planner => "planner",
+            Action::Log => "log",
+            Action::Bookmarks => "bookmarks",
+            Action::Tree => "tree",
+            Action::Table => "table",
+            Action::Compare => "compare",
+            Action::OwnerFilter => "owner-filter",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Select => "select",
+            Action::Back => "back",
+            Action::Home => "home",
+            Action::HistoryBack => "history-back",
+            Action::HistoryForward => "history-forward",
+            Action::Bookmark => "bookmark",
+            Action::Mark => "mark",
+            Action::Pause => "pause",
+            Action::WarpFaster => "warp-faster",
+            Action::WarpSlower => "warp-slower",
+            Action::CancelJob => "cancel-job",
+            Action::Buy => "buy",
+            Action::Sell => "sell",
+            Action::OrderTransfer => "order-transfer",
+            Action::OrderLand => "order-land",
+            Action::OrderMine => "order-mine",
+            Action::OrderUnload => "order-unload",
+            Action::OrderRepeat => "order-repeat",
+            Action::OrderDelete => "order-delete",
+            Action::LogFilter => "log-filter",
+            Action::AutoPause => "auto-pause",
+            Action::BookmarkRename => "bookmark-rename",
+            Action::BookmarkDelete => "bookmark-delete",
+            Action::TableSort => "table-sort",
+            Action::TableKind => "table-kind",
+            Action::TableScope => "table-scope",
+            Action::TableSearch => "table-search",
+            Action::CompareClear => "compare-clear",
+        }
+    }
+
+    /// what the action does, for the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "show this help",
+            Action::NextShip => "control the next ship of the fleet",
+            Action::Fleet => "fleet overview",
+            Action::Orders => "orders of the controlled ship",
+            Action::Shipyard => "shipyard of the inspected station",
+            Action::Production => "refineries of the inspected structure",
+            Action::Power => "power grid of the inspected structure",
+            Action::Market => "market prices and trading",
+            Action::Planner => "transfer planner to the inspected object",
+            Action::Log => "message log",
+            Action::Bookmarks => "bookmarked places",
+            Action::Tree => "tree of every known object",
+            Action::Table => "table of known objects",
+            Action::Compare => "marked objects side by side",
+            Action::OwnerFilter => "cycle the owner filter",
+            Action::Up => "move up",
+            Action::Down => "move down",
+            Action::Left => "move left, or along the breadcrumb",
+            Action::Right => "move right, or along the breadcrumb",
+            Action::Select => "enter or activate the selection",
+            Action::Back => "close the panel, or go back to your ship",
+            Action::Home => "inspect the controlled ship",
+            Action::HistoryBack => "inspect the previous object",
+            Action::HistoryForward => "inspect the next object, after going back",
+            Action::Bookmark => "bookmark the inspected object or selected site, or forget it",
+            Action::Mark => "mark the inspected or selected object for comparison, or unmark it",
+            Action::Pause => "pause or resume",
+            Action::WarpFaster => "warp time faster",
+            Action::WarpSlower => "warp time slower",
+            Action::CancelJob => "cancel the last job",
+            Action::Buy => "buy 100kg",
+            Action::Sell => "sell 100kg",
+            Action::OrderTransfer => "transfer to the inspected object",
+            Action::OrderLand => "land at the selected site",
+            Action::OrderMine => "mine until the cargo is full",
+            Action::OrderUnload => "unload at the inspected object",
+            Action::OrderRepeat => "repeat the queue",
+            Action::OrderDelete => "delete the selected order",
+            Action::LogFilter => "cycle the least severity shown",
+            Action::AutoPause => "toggle pausing on alerts",
+            Action::BookmarkRename => "rename the selected bookmark",
+            Action::BookmarkDelete => "delete the selected bookmark",
+            Action::TableSort => "sort by the selected column, again to reverse",
+            Action::TableKind => "cycle the kind of objects listed",
+            Action::TableScope => "list the inspected system or the whole galaxy",
+            Action::TableSearch => "filter by name",
+            Action::CompareClear => "unmark every object",
+        }
+    }
+
+    /// panel the action is limited to, `None` when it works everywhere
+    pub fn panel(&self) -> Option<Panel> {
+        match self {
+            Action::CancelJob => Some(Panel::Shipyard),
+            Action::Buy | Action::Sell => Some(Panel::Market),
+            Action::OrderTransfer
+            | Action::OrderLand
+            | Action::OrderMine
+            | Action::OrderUnload
+            | Action::OrderRepeat
+            | Action::OrderDelete => Some(Panel::Orders),
+            Action::LogFilter | Action::AutoPause => Some(Panel::Log),
+            Action::BookmarkRename | Action::BookmarkDelete => Some(Panel::Bookmarks),
+            Action::TableSort | Action::TableKind | Action::TableScope | Action::TableSearch => Some(Panel::Table),
+            Action::CompareClear => Some(Panel::Compare),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL.into_iter().find(|action| action.name() == s).ok_or_else(|| format!("unknown action `{s}`"))
+    }
+}
+
+/// a key with its modifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub const fn new(code: KeyCode) -> Self {
+        Key { code, modifiers: KeyModifiers::NONE }
+    }
+
+    pub const fn ctrl(c: char) -> Self {
+        Key { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    // shift is part of the character typed, and control keys match either case
+    fn from(event: KeyEvent) -> Self {
+        match event.code {
+            KeyCode::Char(c) => Key {
+                code: KeyCode::Char(if event.modifiers.contains(KeyModifiers::CONTROL) { c.to_ascii_lowercase() } else { c }),
+                modifiers: event.modifiers - KeyModifiers::SHIFT,
+            },
+            code => Key { code, modifiers: event.modifiers },
+        }
+    }
+}
+
+// names of keys that aren't a single character
+const NAMED: [(&str, KeyCode); 18] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    // separators of the config file
+    ("comma", KeyCode::Char(',')),
+    ("equals", KeyCode::Char('=')),
+    ("minus", KeyCode::Char('-')),
+];
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, name) = match s.strip_prefix("ctrl-") {
+            Some(name) if !name.is_empty() => (KeyModifiers::CONTROL, name),
+            _ => (KeyModifiers::NONE, s),
+        };
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(if modifiers.is_empty() { c } else { c.to_ascii_lowercase() }),
+            _ => match NAMED.iter().find(|(named, _)| named.eq_ignore_ascii_case(name)) {
+                Some((_, code)) => *code,
+                None => match name.to_ascii_lowercase().strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{s}`")),
+                },
+            },
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match (self.code, NAMED.iter().find(|(_, code)| *code == self.code)) {
+            (_, Some((name, _))) => name.to_string(),
+            (KeyCode::Char(c), None) => c.to_string(),
+            (KeyCode::F(n), None) => format!("f{n}"),
+            (code, None) => format!("{code:?}").to_lowercase(),
+        };
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.pad(&format!("ctrl-{name}"))
+        } else {
+            f.pad(&name)
+        }
+    }
+}
+
+/// which keys trigger which actions
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let char = |c| Key::new(KeyCode::Char(c));
+        Self {
+            bindings: vec![
+                (char('q'), Action::Quit),
+                (Key::ctrl('c'), Action::Quit),
+                (char('?'), Action::Help),
+                (Key::new(KeyCode::Tab), Action::NextShip),
+                (char('f'), Action::Fleet),
+                (char('o'), Action::Orders),
+                (char('b'), Action::Shipyard),
+                (char('p'), Action::Production),
+                (char('w'), Action::Power),
+                (char('$'), Action::Market),
+                (char('h'), Action::Planner),
+                (char('n'), Action::Log),
+                (char('k'), Action::Bookmarks),
+                (char('e'), Action::Tree),
+                (char('i'), Action::Table),
+                (char('='), Action::Compare),
+                (char('a'), Action::OwnerFilter),
+                (Key::new(KeyCode::Up), Action::Up),
+                (Key::new(KeyCode::Down), Action::Down),
+                (Key::new(KeyCode::Left), Action::Left),
+                (Key::new(KeyCode::Right), Action::Right),
+                (Key::new(KeyCode::Enter), Action::Select),
+                (Key::new(KeyCode::Esc), Action::Back),
+                (Key::new(KeyCode::Backspace), Action::Home),
+                (char('['), Action::HistoryBack),
+                (char(']'), Action::HistoryForward),
+                (char('*'), Action::Bookmark),
+                (char('v'), Action::Mark),
+                (char(' '), Action::Pause),
+                (char('.'), Action::WarpFaster),
+                (char(','), Action::WarpSlower),
+                (char('x'), Action::CancelJob),
+                (char('+'), Action::Buy),
+                (char('-'), Action::Sell),
+                (char('t'), Action::OrderTransfer),
+                (char('l'), Action::OrderLand),
+                (char('m'), Action::OrderMine),
+                (char('u'), Action::OrderUnload),
+                (char('r'), Action::OrderRepeat),
+                (char('d'), Action::OrderDelete),
+                (Key::new(KeyCode::Delete), Action::OrderDelete),
+                (char('s'), Action::LogFilter),
+                (char('z'), Action::AutoPause),
+                (char('r'), Action::BookmarkRename),
+                (char('d'), Action::BookmarkDelete),
+                (char('s'), Action::TableSort),
+                (char('c'), Action::TableKind),
+                (char('g'), Action::TableScope),
+                (char('/'), Action::TableSearch),
+                (char('x'), Action::CompareClear),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    /// the default bindings with the user's overrides, and anything wrong with them
+    pub fn load() -> (Self, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut problems = vec![];
+        if let Some(path) = Keymap::path() {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => problems.extend(keymap.apply(&text).into_iter().map(|problem| format!("{}: {problem}", path.display()))),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => problems.push(format!("{}: {error}", path.display())),
+            }
+        }
+        problems.extend(keymap.conflicts());
+        (keymap, problems)
+    }
+
+    /// where the user's bindings are read from
+    pub fn path() -> Option<PathBuf> {
+        let config = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(config.join("spacers").join("keys.conf"))
+    }
+
+    /// rebinds the actions listed in a config file, returning the lines that couldn't be read.
+    /// each line replaces the keys of an action, as in `order-delete = d, delete`, and lines
+    /// starting with `#` are comments.
+    pub fn apply(&mut self, text: &str) -> Vec<String> {
+        let mut problems = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line.split_once('=').ok_or_else(|| "expected `action = key, ...`".to_string()).and_then(|(action, keys)| {
+                let action: Action = action.trim().parse()?;
+                let keys = keys.split(',').map(|key| key.trim().parse()).collect::<Result<Vec<Key>, _>>()?;
+                Ok((action, keys))
+            });
+            match parsed {
+                Ok((action, keys)) => {
+                    self.bindings.retain(|(_, bound)| *bound != action);
+                    self.bindings.extend(keys.into_iter().map(|key| (key, action)));
+                }
+                Err(problem) => problems.push(format!("line {}: {problem}", number + 1)),
+            }
+        }
+        problems
+    }
+
+    /// keys bound to actions that can happen in the same panel
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = vec![];
+        for (i, (key, a)) in self.bindings.iter().enumerate() {
+            for (other, b) in &self.bindings[i + 1..] {
+                let overlap = a.panel().is_none() || b.panel().is_none() || a.panel() == b.panel();
+                if key == other && a != b && overlap {
+                    conflicts.push(format!("`{key}` is bound to both {} and {}", a.name(), b.name()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// action the key triggers among those `available`
+    pub fn action(&self, key: Key, available: &[Action]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, action)| *bound == key && available.contains(action))
+            .map(|(_, action)| *action)
+    }
+
+    /// keys bound to the action
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings.iter().filter(|(_, bound)| *bound == action).map(|(key, _)| *key).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_rebinds_actions() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply("# comment\n\nwarp-slower = comma, ctrl-S\n compare = equals \nsell = minus, f5\n");
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(keymap.keys(Action::WarpSlower), [Key::new(KeyCode::Char(',')), Key::ctrl('s')]);
+        assert_eq!(keymap.keys(Action::Compare), [Key::new(KeyCode::Char('='))]);
+        assert_eq!(keymap.keys(Action::Sell), [Key::new(KeyCode::Char('-')), Key::new(KeyCode::F(5))]);
+        assert_eq!(keymap.action(Key::new(KeyCode::Char(',')), &[Action::WarpSlower]), Some(Action::WarpSlower));
+    }
+
+    #[test]
+    fn apply_reports_bad_lines() {
+        let mut keymap = Keymap::default();
+        let problems = keymap.apply("quit = q\nfly = x\npause\nhelp = f13\nback = esc,\n");
+        assert_eq!(
+            problems,
+            [
+                "line 2: unknown action `fly`",
+                "line 3: expected `action = key, ...`",
+                "line 4: unknown key `f13`",
+                "line 5: unknown key ``",
+            ]
+        );
+        // lines that fail leave the action's keys alone
+        assert_eq!(keymap.keys(Action::Back), [Key::new(KeyCode::Esc)]);
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for name in ["comma", "equals", "minus", "space", "ctrl-c", "f12", "x", "?"] {
+            assert_eq!(name.parse::<Key>().unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn conflicts_only_within_a_panel() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.conflicts().is_empty(), "{:?}", keymap.conflicts());
+
+        // both live in their own panel
+        keymap.apply("buy = d");
+        assert!(keymap.conflicts().is_empty());
+        keymap.apply("order-mine = d");
+        assert_eq!(keymap.conflicts(), ["`d` is bound to both order-delete and order-mine"]);
+        // global actions clash with every panel
+        keymap.apply("order-mine = m\npause = d");
+        assert_eq!(keymap.conflicts().len(), 3);
+    }
+}
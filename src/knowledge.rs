@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use crate::{
+    object::{Composition, Object, ObjectHandle, Resource},
+    space::{Mass, Time},
+};
+
+/// what the player's faction has detected of the galaxy.
+///
+/// objects missing from here are hidden from the player entirely.
+#[derive(Debug, Default)]
+pub struct Knowledge {
+    contacts: HashMap<ObjectHandle, Contact>,
+}
+
+/// last known data about a detected object
+#[derive(Clone, Debug)]
+pub struct Contact {
+    /// when the object was last in sensor range
+    pub seen: Time,
+    /// estimated mass at the time it was seen
+    pub mass: Mass,
+    /// relative uncertainty of the mass estimate, 0 being exact
+    pub mass_error: f64,
+    /// only known once the object came within a sensor's resolution
+    pub composition: Option<Composition>,
+    /// asking and bidding price of every resource, for stations with a market
+    pub prices: Vec<(Resource, f64, f64)>,
+}
+
+impl Knowledge {
+    pub fn get(&self, object_handle: ObjectHandle) -> Option<&Contact> {
+        self.contacts.get(&object_handle)
+    }
+
+    pub fn is_known(&self, object_handle: ObjectHandle) -> bool {
+        self.contacts.contains_key(&object_handle)
+    }
+
+    /// records a sensor reading, keeping previously resolved composition
+    pub fn observe(&mut self, object_handle: ObjectHandle, mut contact: Contact) {
+        if let Some(old) = self.contacts.get_mut(&object_handle) {
+            // a more precise reading taken this very instant wins
+            if old.seen.0 == contact.seen.0 && old.mass_error <= contact.mass_error {
+                if old.composition.is_none() {
+                    old.composition = contact.composition;
+                }
+                return;
+            }
+            if contact.composition.is_none() {
+                contact.composition = old.composition.take();
+            }
+        }
+        self.contacts.insert(object_handle, contact);
+    }
+
+    /// drops whatever was known of an object that no longer exists
+    pub fn forget(&mut self, object_handle: ObjectHandle) {
+        self.contacts.remove(&object_handle);
+    }
+
+    /// children of `object` that have been detected
+    pub fn known_children<'a>(&'a self, object: &'a Object) -> impl Iterator<Item = ObjectHandle> + 'a {
+        object
+            .children
+            .iter()
+            .flatten()
+            .map(|(child, _)| *child)
+            .filter(|child| self.is_known(*child))
+    }
+
+    /// time since the object was last seen
+    pub fn age(&self, object_handle: ObjectHandle, now: Time) -> Option<Time> {
+        self.get(object_handle).map(|contact| Time(now.0 - contact.seen.0))
+    }
+}
+
+impl Contact {
+    pub fn has_market(&self) -> bool {
+        !self.prices.is_empty()
+    }
+
+    /// last known asking and bidding price of `resource`
+    pub fn price(&self, resource: Resource) -> Option<(f64, f64)> {
+        self.prices.iter().find(|(r, _, _)| *r == resource).map(|(_, ask, bid)| (*ask, *bid))
+    }
+}
@@ -1,16 +1,61 @@
-use crate::app::App;
+use ratatui::crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+};
+
+use color_eyre::eyre::{bail, eyre};
+
+use crate::{app::App, event::TICK_FPS};
 
 pub mod app;
+pub mod bookmark;
+pub mod change;
+pub mod collision;
+pub mod compare;
 pub mod event;
-pub mod ui;
+pub mod faction;
+pub mod keymap;
+pub mod knowledge;
+pub mod market;
+pub mod message;
 mod object;
+pub mod orders;
+pub mod power;
+pub mod refinery;
+pub mod rng;
+pub mod shipyard;
+pub mod surface;
+pub mod table;
+pub mod trajectory;
+pub mod ui;
 
 pub mod space;
 
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+
+    // `--frame-rate` and `--step-rate` set how often frames are drawn and the simulation steps
+    let (mut frame_rate, mut step_rate) = (TICK_FPS, TICK_FPS);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let rate = match arg.as_str() {
+            "--frame-rate" => &mut frame_rate,
+            "--step-rate" => &mut step_rate,
+            _ => bail!("unknown argument `{arg}`"),
+        };
+        *rate = args
+            .next()
+            .and_then(|value| value.parse().ok())
+            .filter(|value: &f64| value.is_finite() && *value > 0.)
+            .ok_or_else(|| eyre!("`{arg}` takes a positive, finite number of times per second"))?;
+    }
+
+    let app = App::with_rates(frame_rate, step_rate);
     let terminal = ratatui::init();
-    let result = App::new().run(terminal);
+    // once the terminal is taken over it is restored however the app ends
+    let result = execute!(std::io::stdout(), EnableMouseCapture).map_err(Into::into).and_then(|()| app.run(terminal));
+    let released = execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
-    result
+    result?;
+    Ok(released?)
 }
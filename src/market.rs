@@ -0,0 +1,157 @@
+use std::fmt::Display;
+
+use crate::{
+    faction::Standing,
+    object::{ComponentKind, ObjectHandle, Parent, Resource, Structure},
+    space::{Galaxy, Mass, World},
+};
+
+/// stock a station wants of every resource, in kg
+const BASE_STOCK: f64 = 5_000.;
+/// days of refinery consumption a station wants to keep in stock
+const STOCK_DAYS: f64 = 7.;
+
+/// lets a station trade the resources in its cargo for credits
+#[derive(Clone, Debug)]
+pub struct Market {
+    /// difference between the station's selling and buying prices, as a fraction of the price
+    pub spread: f64,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        Self { spread: 0.1 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeError {
+    /// the ship must be docked at the station to trade
+    NotDocked,
+    NoMarket,
+    NotEnoughCredits,
+    NotEnoughStock,
+    NoCargoSpace,
+    /// the station's owner is hostile to the ship's
+    Refused,
+}
+
+impl Display for TradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TradeError::NotDocked => "ship is not docked at a station",
+            TradeError::NoMarket => "no market here",
+            TradeError::NotEnoughCredits => "not enough credits",
+            TradeError::NotEnoughStock => "not enough in stock",
+            TradeError::NoCargoSpace => "no cargo space left",
+            TradeError::Refused => "the station refuses to trade with you",
+        })
+    }
+}
+
+impl Resource {
+    /// price in credits per kg when stock matches demand
+    pub fn base_price(&self) -> f64 {
+        match self {
+            Resource::Hydrogen => 2.,
+            Resource::Helium => 5.,
+            Resource::Rock => 0.5,
+            Resource::Ice => 1.,
+            Resource::Metals => 4.,
+            Resource::Water => 3.,
+            Resource::Fuel => 10.,
+            Resource::Alloys => 15.,
+        }
+    }
+}
+
+impl Market {
+    /// mid price of `resource` at `structure` in credits per kg, rising as stock runs below what
+    /// the station wants and falling when its refineries produce it
+    pub fn price(structure: &Structure, resource: Resource) -> f64 {
+        let mut flow = 0.;
+        for recipe in structure.refineries().filter_map(|refinery| refinery.recipe()) {
+            let batches = 86_400. / recipe.duration.0;
+            flow += recipe.outputs.iter().filter(|(r, _)| *r == resource).map(|(_, mass)| mass * batches).sum::<f64>();
+            flow -= recipe.inputs.iter().filter(|(r, _)| *r == resource).map(|(_, mass)| mass * batches).sum::<f64>();
+        }
+        let wanted = (BASE_STOCK - STOCK_DAYS * flow).max(BASE_STOCK * 0.1);
+        let stock = structure.stored(resource).0.max(1.);
+        resource.base_price() * (wanted / stock).sqrt().clamp(0.25, 4.)
+    }
+
+    /// what the station asks from buyers, in credits per kg
+    pub fn ask(&self, structure: &Structure, resource: Resource) -> f64 {
+        Self::price(structure, resource) * (1. + self.spread / 2.)
+    }
+
+    /// what the station pays sellers, in credits per kg
+    pub fn bid(&self, structure: &Structure, resource: Resource) -> f64 {
+        Self::price(structure, resource) * (1. - self.spread / 2.)
+    }
+}
+
+impl Galaxy {
+    /// buys `mass` of `resource` from the station the ship orbits, returns the credits spent
+    pub fn buy(&mut self, ship: ObjectHandle, resource: Resource, mass: Mass) -> Result<f64, TradeError> {
+        let station = self.trading_station(ship)?;
+        let structure = self.get_object(station).unwrap().structure().unwrap();
+        if structure.stored(resource).0 < mass.0 {
+            return Err(TradeError::NotEnoughStock);
+        }
+        let cost = structure.market().unwrap().ask(structure, resource) * mass.0;
+        if *self.account_mut(ship) < cost {
+            return Err(TradeError::NotEnoughCredits);
+        }
+        if self.get_object(ship).unwrap().structure().is_none_or(|ship| ship.free().0 < mass.0) {
+            return Err(TradeError::NoCargoSpace);
+        }
+
+        self.get_object_mut(station).unwrap().structure_mut().unwrap().unload(resource, mass);
+        self.get_object_mut(ship).unwrap().structure_mut().unwrap().load(resource, mass);
+        *self.account_mut(ship) -= cost;
+        Ok(cost)
+    }
+
+    /// sells `mass` of `resource` to the station the ship orbits, returns the credits earned
+    pub fn sell(&mut self, ship: ObjectHandle, resource: Resource, mass: Mass) -> Result<f64, TradeError> {
+        let station = self.trading_station(ship)?;
+        if self.get_object(ship).unwrap().structure().is_none_or(|ship| ship.stored(resource).0 < mass.0) {
+            return Err(TradeError::NotEnoughStock);
+        }
+        let structure = self.get_object(station).unwrap().structure().unwrap();
+        if structure.free().0 < mass.0 {
+            return Err(TradeError::NoCargoSpace);
+        }
+        let earned = structure.market().unwrap().bid(structure, resource) * mass.0;
+
+        self.get_object_mut(ship).unwrap().structure_mut().unwrap().unload(resource, mass);
+        self.get_object_mut(station).unwrap().structure_mut().unwrap().load(resource, mass);
+        *self.account_mut(ship) += earned;
+        Ok(earned)
+    }
+
+    // credits paying for the ship's trades: its faction's for AI ships, the player's otherwise
+    fn account_mut(&mut self, ship: ObjectHandle) -> &mut f64 {
+        match self.factions().pilot(ship).map(|pilot| pilot.faction) {
+            Some(faction) => &mut self.factions_mut().get_mut(faction).unwrap().credits,
+            None => self.credits_mut(),
+        }
+    }
+
+    /// the station with a market that the ship is docked at
+    pub fn trading_station(&self, ship: ObjectHandle) -> Result<ObjectHandle, TradeError> {
+        let Some(Parent::Relation(station)) = self.get_object(ship).map(|ship| &ship.parent) else {
+            return Err(TradeError::NotDocked);
+        };
+        if !self.relation(ship).is_some_and(|relation| relation.is_connected()) {
+            return Err(TradeError::NotDocked);
+        }
+        match self.get_object(*station).and_then(|station| station.structure()) {
+            Some(_) if self.standing(ship, *station) == Standing::Hostile => Err(TradeError::Refused),
+            Some(structure) if structure.count(ComponentKind::Market) > 0 => Ok(*station),
+            Some(_) => Err(TradeError::NoMarket),
+            None => Err(TradeError::NotDocked),
+        }
+    }
+}
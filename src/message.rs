@@ -0,0 +1,114 @@
+use std::fmt::Display;
+
+use crate::{
+    collision::OrbitError,
+    object::{ObjectHandle, Resource},
+    orders::OrderError,
+    space::{Galaxy, Mass, Time, World},
+};
+
+/// fuel under which a ship of the fleet warns that it is running out
+pub const LOW_FUEL: Mass = Mass(100.);
+
+/// how much a notification matters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Alert,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Alert => "alert",
+        })
+    }
+}
+
+/// something that happened in the simulation the player should hear about
+#[derive(Clone, Debug)]
+pub enum Notification {
+    /// a ship of the fleet reached the target of a transfer
+    Arrived { ship: ObjectHandle, name: &'static str, target: &'static str },
+    /// a ship of the fleet filled its cargo hold mining
+    CargoFull { ship: ObjectHandle, name: &'static str },
+    /// a ship of the fleet went under `LOW_FUEL`
+    LowFuel { ship: ObjectHandle, name: &'static str, fuel: Mass },
+    /// a known object was destroyed in a collision
+    Wrecked { parent: ObjectHandle, name: &'static str },
+    /// the debris of a wreck couldn't hold the orbit and scattered
+    Scattered { parent: ObjectHandle, name: &'static str, error: OrbitError },
+    /// a station of the player couldn't launch what it built, and took its cost back
+    LaunchFailed { station: ObjectHandle, product: &'static str },
+    /// the order queue of a ship of the fleet halted
+    OrderFailed { ship: ObjectHandle, name: &'static str, error: OrderError },
+}
+
+impl Notification {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Notification::Arrived { .. } | Notification::CargoFull { .. } => Severity::Info,
+            Notification::LowFuel { .. }
+            | Notification::OrderFailed { .. }
+            | Notification::Scattered { .. }
+            | Notification::LaunchFailed { .. } => Severity::Warning,
+            Notification::Wrecked { .. } => Severity::Alert,
+        }
+    }
+
+    /// object to look at to follow up on the notification
+    pub fn subject(&self) -> ObjectHandle {
+        match self {
+            Notification::Arrived { ship, .. }
+            | Notification::CargoFull { ship, .. }
+            | Notification::LowFuel { ship, .. }
+            | Notification::OrderFailed { ship, .. } => *ship,
+            Notification::LaunchFailed { station, .. } => *station,
+            Notification::Wrecked { parent, .. } | Notification::Scattered { parent, .. } => *parent,
+        }
+    }
+}
+
+impl Display for Notification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notification::Arrived { name, target, .. } => write!(f, "{name} arrived at {target}"),
+            Notification::CargoFull { name, .. } => write!(f, "{name} has a full cargo hold"),
+            Notification::LowFuel { name, fuel, .. } => write!(f, "{name} is low on fuel, {fuel} left"),
+            Notification::Wrecked { name, .. } => write!(f, "{name} was destroyed in a collision"),
+            Notification::OrderFailed { name, error, .. } => write!(f, "{name} halted its orders: {error}"),
+            Notification::LaunchFailed { product, .. } => write!(f, "{product} couldn't be launched, its cost was refunded"),
+            Notification::Scattered { name, error, .. } => write!(f, "debris of {name} scattered: {error}"),
+        }
+    }
+}
+
+/// a notification and when it happened
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub time: Time,
+    pub notification: Notification,
+}
+
+impl Galaxy {
+    // notifies about a ship of the fleet, leaving the AI's ships out
+    pub(crate) fn notify_fleet(&mut self, ship: ObjectHandle, notification: Notification) {
+        if self.fleet().contains(&ship) {
+            self.notify(notification);
+        }
+    }
+
+    // warns when the ship's fuel just went from `before` to under `LOW_FUEL`
+    pub(crate) fn check_fuel(&mut self, ship: ObjectHandle, before: Mass) {
+        let Some(object) = self.get_object(ship) else { return };
+        let fuel = object.structure().map_or(Mass(0.), |structure| structure.stored(Resource::Fuel));
+        if before.0 >= LOW_FUEL.0 && fuel.0 < LOW_FUEL.0 {
+            let name = object.name;
+            self.notify_fleet(ship, Notification::LowFuel { ship, name, fuel });
+        }
+    }
+}
@@ -1,6 +1,16 @@
-use std::{collections::HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
 
-use crate::space::{Distance, Mass, Position};
+use crate::{
+    faction::Owner,
+    market::Market,
+    power::{PowerBudget, Reactor},
+    refinery::Refinery,
+    shipyard::Shipyard,
+    space::{Distance, Mass, Position},
+};
 
 slotmap::new_key_type! { pub struct ObjectHandle; }
 
@@ -10,6 +20,8 @@ pub struct Object {
     pub children: Option<HashMap<ObjectHandle, Relation>>,
     pub mass: Mass,
     pub name: &'static str,
+    /// `None` for natural objects nobody claims
+    pub owner: Option<Owner>,
     pub kind: ObjectKind,
 }
 
@@ -22,6 +34,59 @@ pub enum Parent {
 #[derive(Debug, Clone)]
 pub enum Relation {
     Orbit(usize),
+    /// moored to a structure: moves with it and can exchange cargo with it
+    Docked,
+    /// resting on a body's surface
+    Landed { location: Location },
+    /// bolted onto a structure as a module: moves with it and shares cargo with it
+    Attached,
+}
+
+/// a point on a body's surface, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+impl Relation {
+    /// distance between the child and its parent's center of mass. landers sit on the surface,
+    /// which only the parent knows about, so they report zero.
+    pub fn distance(&self) -> Distance {
+        match self {
+            Relation::Orbit(radius) => Distance(*radius as f64),
+            Relation::Docked | Relation::Landed { .. } | Relation::Attached => Distance(0.),
+        }
+    }
+
+    /// whether cargo can be moved between the child and its parent
+    pub fn is_connected(&self) -> bool {
+        matches!(self, Relation::Docked | Relation::Attached)
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Relation::Orbit(radius) => write!(f, "orbit {radius}km"),
+            Relation::Docked => write!(f, "docked"),
+            Relation::Landed { location } => write!(f, "landed at {location}"),
+            Relation::Attached => write!(f, "attached"),
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.2}°{} {:.2}°{}",
+            self.latitude.abs(),
+            if self.latitude < 0. { 'S' } else { 'N' },
+            self.longitude.abs(),
+            if self.longitude < 0. { 'W' } else { 'E' },
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -47,6 +112,52 @@ impl Object {
             None => self.children = Some(HashMap::from([(object_handle, relation)])),
         };
     }
+    pub fn structure(&self) -> Option<&Structure> {
+        match &self.kind {
+            ObjectKind::Structure(structure) => Some(structure),
+            _ => None,
+        }
+    }
+    pub fn structure_mut(&mut self) -> Option<&mut Structure> {
+        match &mut self.kind {
+            ObjectKind::Structure(structure) => Some(structure),
+            _ => None,
+        }
+    }
+    /// mass along with whatever its holds carry, as thrusters have to push it
+    pub fn loaded_mass(&self) -> Mass {
+        let cargo = self.structure().map_or(0., |structure| structure.capacity().0 - structure.free().0);
+        Mass(self.mass.0 + cargo)
+    }
+    /// radius of bodies, whose surface can be landed on
+    pub fn radius(&self) -> Option<Distance> {
+        match &self.kind {
+            ObjectKind::Body(body) => Some(body.radius),
+            _ => None,
+        }
+    }
+    pub fn composition(&self) -> Option<&Composition> {
+        match &self.kind {
+            ObjectKind::Body(body) => Some(&body.composition),
+            ObjectKind::Field(field) => Some(&field.composition),
+            ObjectKind::Structure(_) => None,
+        }
+    }
+    pub fn composition_mut(&mut self) -> Option<&mut Composition> {
+        match &mut self.kind {
+            ObjectKind::Body(body) => Some(&mut body.composition),
+            ObjectKind::Field(field) => Some(&mut field.composition),
+            ObjectKind::Structure(_) => None,
+        }
+    }
+    pub fn remove_child(&mut self, object_handle: ObjectHandle) -> Option<Relation> {
+        let children = self.children.as_mut()?;
+        let relation = children.remove(&object_handle);
+        if children.is_empty() {
+            self.children = None;
+        }
+        relation
+    }
     pub fn children_count(&self) -> usize {
         match &self.children {
             Some(children) => children.len(),
@@ -82,13 +193,106 @@ pub enum FieldMorphology {
     },
 }
 
+/// shares of the elements in a mix, summing to 1
 #[derive(Clone, Debug)]
 pub struct Composition {
-    pub hydrogen: f32,
-    pub helium: f32,
-    pub rock: f32,
-    pub ice: f32,
-    pub metals: f32,
+    hydrogen: f32,
+    helium: f32,
+    rock: f32,
+    ice: f32,
+    metals: f32,
+}
+
+/// why a mix of elements can't make a composition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositionError {
+    /// an element's share or mass is negative
+    Negative(Resource),
+    /// an element's share or mass is infinite or not a number
+    NonFinite(Resource),
+    /// there is nothing in the mix
+    Empty,
+}
+
+impl Display for CompositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompositionError::Negative(resource) => write!(f, "negative amount of {resource}"),
+            CompositionError::NonFinite(resource) => write!(f, "amount of {resource} is not a finite number"),
+            CompositionError::Empty => f.pad("empty mix"),
+        }
+    }
+}
+
+impl Composition {
+    /// the raw resources a composition is made of
+    pub const ELEMENTS: [Resource; 5] = [Resource::Hydrogen, Resource::Helium, Resource::Rock, Resource::Ice, Resource::Metals];
+
+    /// a composition from amounts of each element in any unit, scaled so the shares sum to 1
+    pub fn new(hydrogen: f32, helium: f32, rock: f32, ice: f32, metals: f32) -> Result<Self, CompositionError> {
+        let amounts = [hydrogen, helium, rock, ice, metals];
+        for (resource, amount) in Self::ELEMENTS.into_iter().zip(amounts) {
+            if !amount.is_finite() {
+                return Err(CompositionError::NonFinite(resource));
+            }
+            if amount < 0. {
+                return Err(CompositionError::Negative(resource));
+            }
+        }
+        // summed wide so that huge amounts can't overflow
+        let total: f64 = amounts.iter().map(|amount| *amount as f64).sum();
+        if total <= 0. {
+            return Err(CompositionError::Empty);
+        }
+        let [hydrogen, helium, rock, ice, metals] = amounts.map(|amount| (amount as f64 / total) as f32);
+        Ok(Composition { hydrogen, helium, rock, ice, metals })
+    }
+
+    /// the composition of the elements' masses put together, ignoring refined goods
+    pub fn from_masses(masses: &[(Resource, Mass)]) -> Result<Self, CompositionError> {
+        let amount = |element| masses.iter().filter(|(resource, _)| *resource == element).map(|(_, mass)| mass.0).sum::<f64>() as f32;
+        let [hydrogen, helium, rock, ice, metals] = Self::ELEMENTS.map(amount);
+        Composition::new(hydrogen, helium, rock, ice, metals)
+    }
+
+    /// fraction of the mix made of `resource`
+    pub fn get(&self, resource: Resource) -> f32 {
+        match resource {
+            Resource::Hydrogen => self.hydrogen,
+            Resource::Helium => self.helium,
+            Resource::Rock => self.rock,
+            Resource::Ice => self.ice,
+            Resource::Metals => self.metals,
+            Resource::Water | Resource::Fuel | Resource::Alloys => 0.,
+        }
+    }
+
+    /// mass of each element in `mass` of the mix
+    pub fn scale(&self, mass: Mass) -> [(Resource, Mass); 5] {
+        Self::ELEMENTS.map(|element| (element, Mass(mass.0 * self.get(element) as f64)))
+    }
+
+    /// the mix of `mass` of this one and `other_mass` of the other
+    pub fn mix(&self, mass: Mass, other: &Composition, other_mass: Mass) -> Result<Self, CompositionError> {
+        Composition::from_masses(&[self.scale(mass), other.scale(other_mass)].concat())
+    }
+
+    /// what is left of `mass` of this mix once `removed_mass` of the other is taken out of it.
+    /// taking out more of an element than there is is an error, short of rounding.
+    pub fn subtract(&self, mass: Mass, removed: &Composition, removed_mass: Mass) -> Result<Self, CompositionError> {
+        let mut left = self.scale(mass);
+        for ((resource, mass), (_, taken)) in left.iter_mut().zip(removed.scale(removed_mass)) {
+            mass.0 -= taken.0;
+            if mass.0 < 0. {
+                // tolerate what floats lose along the way
+                if mass.0 < -1e-6 * removed_mass.0.abs().max(1.) {
+                    return Err(CompositionError::Negative(*resource));
+                }
+                mass.0 = 0.;
+            }
+        }
+        Composition::from_masses(&left)
+    }
 }
 
 impl Default for Composition {
@@ -103,34 +307,233 @@ impl Default for Composition {
     }
 }
 
+/// anything that can be stored in cargo
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resource {
+    Hydrogen,
+    Helium,
+    Rock,
+    Ice,
+    Metals,
+    // refined goods
+    Water,
+    Fuel,
+    Alloys,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 8] = [
+        Resource::Hydrogen,
+        Resource::Helium,
+        Resource::Rock,
+        Resource::Ice,
+        Resource::Metals,
+        Resource::Water,
+        Resource::Fuel,
+        Resource::Alloys,
+    ];
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Resource::Hydrogen => "hydrogen",
+            Resource::Helium => "helium",
+            Resource::Rock => "rock",
+            Resource::Ice => "ice",
+            Resource::Metals => "metals",
+            Resource::Water => "water",
+            Resource::Fuel => "fuel",
+            Resource::Alloys => "alloys",
+        })
+    }
+}
+
+impl Display for Composition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elements = [
+            ("hydrogen", self.hydrogen),
+            ("helium", self.helium),
+            ("rock", self.rock),
+            ("ice", self.ice),
+            ("metals", self.metals),
+        ];
+        let mut first = true;
+        for (name, fraction) in elements.into_iter().filter(|(_, fraction)| *fraction > 0.) {
+            if !first {
+                write!(f, ", ")?;
+            }
+            write!(f, "{name} {:.0}%", fraction * 100.)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Structure {
     components: Vec<Component>,
     index: ComponentIndex,
+    /// grid balance as of the last step
+    pub power: PowerBudget,
 }
 #[derive(Clone, Debug)]
 pub enum Component {
-    Reactor(()),
-    Cargo,
+    Reactor(Reactor),
+    Cargo(Cargo),
     Thruster,
     Drill,
+    Sensor(Sensor),
+    Shipyard(Shipyard),
+    Refinery(Refinery),
+    Market(Market),
 }
 #[repr(u8)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ComponentKind {
     Reactor,
     Cargo,
     Thruster,
     Drill,
+    Sensor,
+    Shipyard,
+    Refinery,
+    Market,
 }
 #[derive(Clone, Debug)]
 pub struct ComponentIndex {
     by_kind: Vec<Vec<usize>>,
 }
 
+/// hold for resources
+#[derive(Clone, Debug, Default)]
+pub struct Cargo {
+    pub capacity: Mass,
+    pub contents: BTreeMap<Resource, Mass>,
+}
+
+/// detects objects and reports them to the owner's knowledge
+#[derive(Clone, Debug)]
+pub struct Sensor {
+    /// objects closer than this are detected
+    pub range: Distance,
+    /// objects closer than this have their composition resolved
+    pub resolution: Distance,
+}
+
+impl Component {
+    pub fn kind(&self) -> ComponentKind {
+        match self {
+            Component::Reactor(_) => ComponentKind::Reactor,
+            Component::Cargo(_) => ComponentKind::Cargo,
+            Component::Thruster => ComponentKind::Thruster,
+            Component::Drill => ComponentKind::Drill,
+            Component::Sensor(_) => ComponentKind::Sensor,
+            Component::Shipyard(_) => ComponentKind::Shipyard,
+            Component::Refinery(_) => ComponentKind::Refinery,
+            Component::Market(_) => ComponentKind::Market,
+        }
+    }
+}
+
+impl Cargo {
+    pub fn new(capacity: Mass) -> Self {
+        Self { capacity, contents: BTreeMap::new() }
+    }
+    pub fn stored(&self) -> Mass {
+        Mass(self.contents.values().map(|mass| mass.0).sum())
+    }
+    pub fn free(&self) -> Mass {
+        Mass((self.capacity.0 - self.stored().0).max(0.))
+    }
+    pub fn get(&self, resource: Resource) -> Mass {
+        self.contents.get(&resource).copied().unwrap_or_default()
+    }
+    /// loads as much of `mass` as fits, returning the loaded mass
+    pub fn load(&mut self, resource: Resource, mass: Mass) -> Mass {
+        let loaded = Mass(mass.0.min(self.free().0).max(0.));
+        self.contents.entry(resource).or_default().0 += loaded.0;
+        loaded
+    }
+    /// unloads up to `mass`, returning the unloaded mass
+    pub fn unload(&mut self, resource: Resource, mass: Mass) -> Mass {
+        let Some(stored) = self.contents.get_mut(&resource) else { return Mass(0.) };
+        let unloaded = Mass(mass.0.min(stored.0).max(0.));
+        stored.0 -= unloaded.0;
+        if stored.0 <= 0. {
+            self.contents.remove(&resource);
+        }
+        unloaded
+    }
+}
+
+impl ComponentKind {
+    pub const ALL: [ComponentKind; 8] = [
+        ComponentKind::Reactor,
+        ComponentKind::Cargo,
+        ComponentKind::Thruster,
+        ComponentKind::Drill,
+        ComponentKind::Sensor,
+        ComponentKind::Shipyard,
+        ComponentKind::Refinery,
+        ComponentKind::Market,
+    ];
+}
+
+impl Display for ComponentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            ComponentKind::Reactor => "reactor",
+            ComponentKind::Cargo => "cargo",
+            ComponentKind::Thruster => "thruster",
+            ComponentKind::Drill => "drill",
+            ComponentKind::Sensor => "sensor",
+            ComponentKind::Shipyard => "shipyard",
+            ComponentKind::Refinery => "refinery",
+            ComponentKind::Market => "market",
+        })
+    }
+}
+
 impl Structure {
-    // example
-    fn reactors(&self) -> impl Iterator<Item = &()> {
+    pub fn new(components: Vec<Component>) -> Self {
+        let mut by_kind = vec![vec![]; ComponentKind::ALL.len()];
+        for (i, component) in components.iter().enumerate() {
+            by_kind[component.kind() as usize].push(i);
+        }
+        Self {
+            components,
+            index: ComponentIndex { by_kind },
+            power: PowerBudget::default(),
+        }
+    }
+
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    pub fn add_component(&mut self, component: Component) {
+        self.index.by_kind[component.kind() as usize].push(self.components.len());
+        self.components.push(component);
+    }
+
+    pub fn count(&self, kind: ComponentKind) -> usize {
+        self.index.by_kind[kind as usize].len()
+    }
+
+    /// whether the `nth` component of `kind` got power during the last step
+    pub fn is_powered(&self, kind: ComponentKind, nth: usize) -> bool {
+        self.index.by_kind[kind as usize]
+            .get(nth)
+            .is_some_and(|i| !self.power.shed.contains(i))
+    }
+
+    /// how many components of `kind` got power during the last step
+    pub fn powered(&self, kind: ComponentKind) -> usize {
+        (0..self.count(kind)).filter(|nth| self.is_powered(kind, *nth)).count()
+    }
+
+    pub fn reactors(&self) -> impl Iterator<Item = &Reactor> {
         self.index.by_kind[ComponentKind::Reactor as usize]
             .iter()
             .map(|&i| match &self.components[i] {
@@ -138,13 +541,112 @@ impl Structure {
                 _ => unreachable!(),
             })
     }
+
+    pub fn cargo(&self) -> impl Iterator<Item = &Cargo> {
+        self.index.by_kind[ComponentKind::Cargo as usize]
+            .iter()
+            .map(|&i| match &self.components[i] {
+                Component::Cargo(a) => a,
+                _ => unreachable!(),
+            })
+    }
+
+    pub fn cargo_mut(&mut self) -> impl Iterator<Item = &mut Cargo> {
+        self.components.iter_mut().filter_map(|component| match component {
+            Component::Cargo(a) => Some(a),
+            _ => None,
+        })
+    }
+
+    /// total mass of `resource` across all cargo holds
+    pub fn stored(&self, resource: Resource) -> Mass {
+        Mass(self.cargo().map(|cargo| cargo.get(resource).0).sum())
+    }
+
+    pub fn capacity(&self) -> Mass {
+        Mass(self.cargo().map(|cargo| cargo.capacity.0).sum())
+    }
+
+    pub fn free(&self) -> Mass {
+        Mass(self.cargo().map(|cargo| cargo.free().0).sum())
+    }
+
+    /// spreads `mass` over the cargo holds, returning the loaded mass
+    pub fn load(&mut self, resource: Resource, mass: Mass) -> Mass {
+        let mut loaded = 0.;
+        for cargo in self.cargo_mut() {
+            loaded += cargo.load(resource, Mass(mass.0 - loaded)).0;
+        }
+        Mass(loaded)
+    }
+
+    /// takes up to `mass` out of the cargo holds, returning the unloaded mass
+    pub fn unload(&mut self, resource: Resource, mass: Mass) -> Mass {
+        let mut unloaded = 0.;
+        for cargo in self.cargo_mut() {
+            unloaded += cargo.unload(resource, Mass(mass.0 - unloaded)).0;
+        }
+        Mass(unloaded)
+    }
+
+    pub fn shipyards(&self) -> impl Iterator<Item = &Shipyard> {
+        self.index.by_kind[ComponentKind::Shipyard as usize]
+            .iter()
+            .map(|&i| match &self.components[i] {
+                Component::Shipyard(a) => a,
+                _ => unreachable!(),
+            })
+    }
+
+    pub fn shipyards_mut(&mut self) -> impl Iterator<Item = &mut Shipyard> {
+        self.components.iter_mut().filter_map(|component| match component {
+            Component::Shipyard(a) => Some(a),
+            _ => None,
+        })
+    }
+
+    pub fn refineries(&self) -> impl Iterator<Item = &Refinery> {
+        self.index.by_kind[ComponentKind::Refinery as usize]
+            .iter()
+            .map(|&i| match &self.components[i] {
+                Component::Refinery(a) => a,
+                _ => unreachable!(),
+            })
+    }
+
+    pub fn refineries_mut(&mut self) -> impl Iterator<Item = &mut Refinery> {
+        self.components.iter_mut().filter_map(|component| match component {
+            Component::Refinery(a) => Some(a),
+            _ => None,
+        })
+    }
+
+    pub fn market(&self) -> Option<&Market> {
+        self.index.by_kind[ComponentKind::Market as usize]
+            .first()
+            .map(|&i| match &self.components[i] {
+                Component::Market(a) => a,
+                _ => unreachable!(),
+            })
+    }
+
+    pub fn sensors(&self) -> impl Iterator<Item = &Sensor> {
+        self.index.by_kind[ComponentKind::Sensor as usize]
+            .iter()
+            .map(|&i| match &self.components[i] {
+                Component::Sensor(a) => a,
+                _ => unreachable!(),
+            })
+    }
 }
 
 
+#[derive(Default)]
 pub struct ObjectBuilder {
     pub children: Option<Vec<(ObjectBuilder, Relation)>>,
     pub mass: Option<Mass>,
     pub name: Option<&'static str>,
+    pub owner: Option<Owner>,
     pub kind: Option<ObjectKind>,
 }
 
@@ -153,17 +655,6 @@ pub enum ParentBuilder {
     Relation(ObjectHandle, Relation),
 }
 
-impl Default for ObjectBuilder {
-    fn default() -> Self {
-        Self {
-            children: None,
-            mass: None,
-            name: None,
-            kind: None
-        }
-    }
-}
-
 impl ObjectBuilder {
     pub fn name(mut self, name: &'static str) -> Self {
         self.name = Some(name);
@@ -184,5 +675,62 @@ impl ObjectBuilder {
         self.kind = Some(kind);
         self
     }
+    pub fn owner(mut self, owner: Owner) -> Self {
+        self.owner = Some(owner);
+        self
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composition_is_normalised() {
+        let composition = Composition::new(0., 0., 3., 0., 1.).unwrap();
+        assert_eq!(composition.get(Resource::Rock), 0.75);
+        assert_eq!(composition.get(Resource::Metals), 0.25);
+        assert_eq!(composition.get(Resource::Fuel), 0.);
+    }
+
+    #[test]
+    fn composition_rejects_bad_amounts() {
+        assert_eq!(Composition::new(0., -1., 1., 0., 0.).unwrap_err(), CompositionError::Negative(Resource::Helium));
+        assert_eq!(Composition::new(0., 0., 1., f32::NAN, 0.).unwrap_err(), CompositionError::NonFinite(Resource::Ice));
+        assert_eq!(Composition::new(f32::INFINITY, 0., 0., 0., 0.).unwrap_err(), CompositionError::NonFinite(Resource::Hydrogen));
+        assert_eq!(Composition::new(0., 0., f32::NEG_INFINITY, 0., 0.).unwrap_err(), CompositionError::NonFinite(Resource::Rock));
+        assert_eq!(Composition::new(0., 0., 0., 0., 0.).unwrap_err(), CompositionError::Empty);
+        // large but finite amounts still add up
+        let huge = Composition::new(f32::MAX, 0., f32::MAX, 0., 0.).unwrap();
+        assert_eq!(huge.get(Resource::Hydrogen), 0.5);
+        assert_eq!(Composition::from_masses(&[(Resource::Fuel, Mass(10.))]).unwrap_err(), CompositionError::Empty);
+    }
+
+    #[test]
+    fn compositions_mix_by_mass() {
+        let rock = Composition::new(0., 0., 1., 0., 0.).unwrap();
+        let ore = Composition::new(0., 0., 1., 0., 3.).unwrap();
+        let mix = rock.mix(Mass(2_000.), &ore, Mass(2_000.)).unwrap();
+        assert_eq!(mix.get(Resource::Rock), 0.625);
+        assert_eq!(mix.get(Resource::Metals), 0.375);
+
+        // nothing of either side is nothing at all
+        assert_eq!(rock.mix(Mass(0.), &ore, Mass(0.)).unwrap_err(), CompositionError::Empty);
+        assert_eq!(rock.mix(Mass(-1.), &ore, Mass(0.)).unwrap_err(), CompositionError::Negative(Resource::Rock));
+    }
+
+    #[test]
+    fn compositions_subtract_by_mass() {
+        let body = Composition::new(0., 0., 3., 0., 1.).unwrap();
+        let ore = Composition::new(0., 0., 0., 0., 1.).unwrap();
+        let left = body.subtract(Mass(4_000.), &ore, Mass(500.)).unwrap();
+        assert_eq!(left.get(Resource::Rock), 3. / 3.5);
+        assert_eq!(left.get(Resource::Metals), 0.5 / 3.5);
+
+        // taking out a mix of its own kind leaves it as it was
+        assert_eq!(body.subtract(Mass(4_000.), &body, Mass(1_000.)).unwrap().get(Resource::Rock), 0.75);
+        // but not more of an element than there is, nor everything
+        assert_eq!(body.subtract(Mass(4_000.), &ore, Mass(1_500.)).unwrap_err(), CompositionError::Negative(Resource::Metals));
+        assert_eq!(body.subtract(Mass(4_000.), &body, Mass(4_000.)).unwrap_err(), CompositionError::Empty);
+    }
+}
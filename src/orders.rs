@@ -0,0 +1,574 @@
+use std::fmt::Display;
+
+use crate::{
+    collision::OrbitError,
+    message::Notification,
+    object::{ComponentKind, ObjectHandle, Parent, Relation, Resource},
+    space::{Distance, Galaxy, Mass, Time, World},
+    surface::fuel_for,
+    trajectory::TransferPlan,
+};
+
+/// cruising speed given by each thruster, in km/s
+const THRUSTER_SPEED: f64 = 20.;
+/// mass extracted by each drill, in kg/s
+const DRILL_RATE: f64 = 5.;
+/// how late a ship may leave for a planned window, in s
+const WINDOW_SLACK: f64 = 60.;
+/// how many orders a ship may complete in a single step, so that a queue of instant orders can't
+/// spin forever
+const MAX_ORDERS_PER_STEP: usize = 16;
+
+/// an instruction for a ship's autopilot
+#[derive(Clone, Debug)]
+pub enum Order {
+    /// move into orbit of the target
+    Transfer(ObjectHandle),
+    /// wait for the planned window, then burn its delta-v to reach the target in its flight time
+    Window(ObjectHandle, TransferPlan),
+    /// mine the orbited object until the cargo is full
+    Mine,
+    /// go to the target and move all cargo into its holds
+    Unload(ObjectHandle),
+    /// go to the body and land at one of its surface sites
+    Land(ObjectHandle, usize),
+    /// start over from the first order
+    Repeat,
+}
+
+/// progress of the order being executed
+#[derive(Clone, Debug, Default)]
+pub enum OrderState {
+    /// waiting to start the current order
+    #[default]
+    Idle,
+    /// moving towards the target, `elapsed` out of `duration`, from `origin` to `arrival` away
+    /// from `common`, the closest parent of the ship and target when it left
+    Transit { elapsed: Time, duration: Time, common: ObjectHandle, origin: Distance, arrival: Distance },
+    /// working on the order, from 0 to 1
+    Working(f64),
+    /// the queue is halted until it is edited
+    Failed(OrderError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderError {
+    NoThrusters,
+    NoDrill,
+    NoPower,
+    NoCargo,
+    NothingToMine,
+    TargetGone,
+    TargetFull,
+    Unreachable,
+    DockingRefused,
+    NoSite,
+    NoFuel,
+    /// the ship wasn't ready to leave when the planned window opened
+    MissedWindow,
+    /// the target is the ship or something it carries
+    OwnTarget,
+    InvalidOrbit(OrbitError),
+}
+
+/// orders of a single ship, executed one after the other
+#[derive(Clone, Debug, Default)]
+pub struct OrderQueue {
+    pub orders: Vec<Order>,
+    /// index of the order being executed, past the end once all are done
+    pub current: usize,
+    pub state: OrderState,
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let OrderError::InvalidOrbit(error) = self {
+            return write!(f, "can't arrive: {error}");
+        }
+        f.write_str(match self {
+            OrderError::NoThrusters => "ship has no thrusters",
+            OrderError::NoDrill => "ship has no drill",
+            OrderError::NoPower => "not enough power",
+            OrderError::NoCargo => "no cargo hold",
+            OrderError::NothingToMine => "nothing to mine here",
+            OrderError::TargetGone => "target no longer exists",
+            OrderError::TargetFull => "target cargo is full",
+            OrderError::Unreachable => "target is out of reach",
+            OrderError::DockingRefused => "target refused docking",
+            OrderError::NoSite => "no such surface site",
+            OrderError::NoFuel => "not enough fuel",
+            OrderError::MissedWindow => "missed the transfer window",
+            OrderError::OwnTarget => "target is carried by the ship",
+            OrderError::InvalidOrbit(_) => unreachable!(),
+        })
+    }
+}
+
+impl OrderState {
+    /// completion of the current order, from 0 to 1
+    pub fn progress(&self) -> Option<f64> {
+        match self {
+            OrderState::Transit { elapsed, duration, .. } => Some((elapsed.0 / duration.0).min(1.)),
+            OrderState::Working(progress) => Some(*progress),
+            _ => None,
+        }
+    }
+}
+
+impl OrderQueue {
+    pub fn is_done(&self) -> bool {
+        self.current >= self.orders.len()
+    }
+
+    pub fn current_order(&self) -> Option<&Order> {
+        self.orders.get(self.current)
+    }
+
+    pub fn insert(&mut self, index: usize, order: Order) {
+        let index = index.min(self.orders.len());
+        self.orders.insert(index, order);
+        if index < self.current {
+            self.current += 1;
+        } else if index == self.current {
+            // the new order runs next, from the start
+            self.state = OrderState::Idle;
+        }
+        self.edited();
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Order> {
+        if index >= self.orders.len() {
+            return None;
+        }
+        let order = self.orders.remove(index);
+        if index < self.current {
+            self.current -= 1;
+        } else if index == self.current {
+            // the running order is gone, start the next one fresh
+            self.state = OrderState::Idle;
+        }
+        self.edited();
+        Some(order)
+    }
+
+    pub fn clear(&mut self) {
+        *self = OrderQueue::default();
+    }
+
+    // editing a finished or failed queue resumes it
+    fn edited(&mut self) {
+        if matches!(self.state, OrderState::Failed(_)) {
+            self.state = OrderState::Idle;
+        }
+        if self.current > self.orders.len() {
+            self.current = self.orders.len();
+        }
+    }
+}
+
+impl Galaxy {
+    /// advances the order queue of every ship, the player's and the AI's, by `dt`
+    pub(crate) fn run_orders(&mut self, dt: Time) {
+        let mut ships: Vec<ObjectHandle> = self.fleet().to_vec();
+        ships.extend(self.factions().pilots().map(|(ship, _)| ship));
+        for ship in ships {
+            self.run_queue(ship, dt);
+        }
+    }
+
+    fn set_order_state(&mut self, ship: ObjectHandle, state: OrderState) {
+        if let Some(queue) = self.get_orders_mut(ship) {
+            queue.state = state;
+        }
+    }
+
+    fn run_queue(&mut self, ship: ObjectHandle, dt: Time) {
+        let mut budget = dt.0;
+        for _ in 0..MAX_ORDERS_PER_STEP {
+            let Some(queue) = self.get_orders_mut(ship) else { return };
+            if matches!(queue.state, OrderState::Failed(_)) {
+                return;
+            }
+            let Some(order) = queue.current_order().cloned() else {
+                queue.state = OrderState::Idle;
+                return;
+            };
+
+            let result = match order {
+                Order::Transfer(target) => self.transfer(ship, target, None, &mut budget),
+                Order::Window(target, plan) => self.transfer(ship, target, Some(plan), &mut budget),
+                Order::Mine => self.mine(ship, &mut budget),
+                Order::Unload(target) => match self.transfer(ship, target, None, &mut budget) {
+                    Ok(true) => self.unload(ship, target),
+                    other => other,
+                },
+                Order::Land(body, site) => match self.transfer(ship, body, None, &mut budget) {
+                    Ok(true) => self.land(ship, body, site),
+                    other => other,
+                },
+                Order::Repeat => {
+                    let Some(queue) = self.get_orders_mut(ship) else { return };
+                    queue.current = 0;
+                    queue.state = OrderState::Idle;
+                    continue;
+                }
+            };
+
+            let Some(queue) = self.get_orders_mut(ship) else { return };
+            match result {
+                Ok(true) => {
+                    queue.current += 1;
+                    queue.state = OrderState::Idle;
+                }
+                Ok(false) => return,
+                Err(error) => {
+                    queue.state = OrderState::Failed(error);
+                    let name = self.get_object(ship).unwrap().name;
+                    self.notify_fleet(ship, Notification::OrderFailed { ship, name, error });
+                    return;
+                }
+            }
+            if budget <= 0. {
+                return;
+            }
+        }
+    }
+
+    // moves the ship into orbit of `target`, along the plan if there is one, returns whether it
+    // arrived
+    fn transfer(
+        &mut self,
+        ship: ObjectHandle,
+        target: ObjectHandle,
+        plan: Option<TransferPlan>,
+        budget: &mut f64,
+    ) -> Result<bool, OrderError> {
+        if self.get_object(target).is_none() {
+            return Err(OrderError::TargetGone);
+        }
+        // moving under itself would make the ship its own ancestor
+        if self.ancestry(target).contains(&ship) {
+            return Err(OrderError::OwnTarget);
+        }
+        // ships in transit may be captured by their target before they arrive
+        let in_transit = matches!(self.get_orders(ship).map(|queue| &queue.state), Some(OrderState::Transit { .. }));
+        if !in_transit && matches!(self.get_object(ship).unwrap().parent, Parent::Relation(parent) if parent == target) {
+            return Ok(true);
+        }
+
+        let (elapsed, duration, common, origin, arrival) = match self.get_orders(ship).map(|queue| &queue.state) {
+            Some(&OrderState::Transit { elapsed, duration, common, origin, arrival }) => (elapsed, duration, common, origin, arrival),
+            _ => {
+                let structure = self.get_object(ship).unwrap().structure().ok_or(OrderError::NoThrusters)?;
+                if structure.count(ComponentKind::Thruster) == 0 {
+                    return Err(OrderError::NoThrusters);
+                }
+                let thrusters = structure.powered(ComponentKind::Thruster);
+                if thrusters == 0 {
+                    return Err(OrderError::NoPower);
+                }
+                let distance = self.distance(ship, target).ok_or(OrderError::Unreachable)?;
+                self.validate_orbit(target, &self.parking_orbit(ship, target)).map_err(OrderError::InvalidOrbit)?;
+                let common = self.common_ancestor(ship, target).ok_or(OrderError::Unreachable)?;
+                let (origin, arrival) = self.transfer_radii(ship, target).ok_or(OrderError::Unreachable)?;
+
+                if let Some(plan) = plan {
+                    // the ship's own clock, behind the world's by what is left of the step
+                    let now = self.get_time().0 - *budget;
+                    if now > plan.departure.0 + WINDOW_SLACK {
+                        return Err(OrderError::MissedWindow);
+                    }
+                    let wait = plan.departure.0 - now;
+                    if wait >= *budget {
+                        *budget = 0.;
+                        return Ok(false);
+                    }
+                    *budget -= wait.max(0.);
+                }
+
+                // burning is the last step, so a ship that can't leave keeps its fuel
+                let mut delta_v = plan.map_or(0., |plan| plan.delta_v);
+                if matches!(self.relation(ship), Some(Relation::Landed { .. }))
+                    && let Parent::Relation(body) = self.get_object(ship).unwrap().parent
+                {
+                    delta_v += self.get_object(body).unwrap().landing_delta_v().unwrap_or(0.);
+                }
+                if delta_v > 0. {
+                    self.burn(ship, delta_v)?;
+                }
+                let duration = plan.map_or(Time(distance.0 / (THRUSTER_SPEED * thrusters as f64)), |plan| plan.duration);
+                (Time(0.), duration, common, origin, arrival)
+            }
+        };
+
+        let elapsed = Time(elapsed.0 + *budget);
+        if elapsed.0 < duration.0 {
+            *budget = 0.;
+            self.set_order_state(ship, OrderState::Transit { elapsed, duration, common, origin, arrival });
+            let radius = origin.0 + (arrival.0 - origin.0) * elapsed.0 / duration.0;
+            self.follow_transfer(ship, target, common, Distance(radius));
+            return Ok(false);
+        }
+
+        *budget = elapsed.0 - duration.0;
+        let relation = self.parking_orbit(ship, target);
+        self.reparent(ship, target, relation);
+        self.set_order_state(ship, OrderState::Idle);
+        let (name, target) = (self.get_object(ship).unwrap().name, self.get_object(target).unwrap().name);
+        self.notify_fleet(ship, Notification::Arrived { ship, name, target });
+        Ok(true)
+    }
+
+    // fills the cargo from the orbited object, returns whether the cargo is full
+    fn mine(&mut self, ship: ObjectHandle, budget: &mut f64) -> Result<bool, OrderError> {
+        if *budget <= 0. {
+            return Ok(false);
+        }
+        let object = self.get_object(ship).unwrap();
+        let Parent::Relation(parent) = object.parent else {
+            return Err(OrderError::NothingToMine);
+        };
+        let structure = object.structure().ok_or(OrderError::NoDrill)?;
+        if structure.count(ComponentKind::Drill) == 0 {
+            return Err(OrderError::NoDrill);
+        }
+        let drills = structure.powered(ComponentKind::Drill);
+        if drills == 0 {
+            return Err(OrderError::NoPower);
+        }
+        let (capacity, free) = (structure.capacity(), structure.free());
+        if capacity.0 <= 0. {
+            return Err(OrderError::NoCargo);
+        }
+        // landers mine the deposit of their site rather than the body as a whole
+        let site = self.landing_site(ship).map(|(_, site)| site);
+        let composition = match site {
+            Some(site) if self.sites(parent)[site].remaining.0 <= 0. => return Err(OrderError::NothingToMine),
+            Some(site) => self.sites(parent)[site].deposit.clone(),
+            None => self
+                .get_object(parent)
+                .and_then(|parent| parent.composition())
+                .cloned()
+                .ok_or(OrderError::NothingToMine)?,
+        };
+
+        let rate = DRILL_RATE * drills as f64;
+        let mut mined = (rate * *budget).min(free.0);
+        if let Some(site) = site {
+            mined = mined.min(self.sites(parent)[site].remaining.0);
+        }
+        let structure = self.get_object_mut(ship).unwrap().structure_mut().unwrap();
+        let mut loaded = 0.;
+        for (resource, mass) in composition.scale(Mass(mined)) {
+            loaded += structure.load(resource, mass).0;
+        }
+        let (stored, free) = (structure.capacity().0 - structure.free().0, structure.free().0);
+        let mass = self.get_object(parent).unwrap().mass;
+        self.set_mass(parent, Mass(mass.0 - loaded));
+        if let Some(site) = site {
+            self.sites_mut(parent)[site].remaining.0 -= loaded;
+        }
+        // what was mined leaves the body, shifting what the rest of it is made of
+        let left = self.get_object(parent).and_then(|body| body.composition()).map(|body| body.subtract(mass, &composition, Mass(loaded)));
+        if let Some(Ok(left)) = left
+            && let Some(body) = self.get_object_mut(parent).and_then(|body| body.composition_mut())
+        {
+            *body = left;
+        }
+
+        // anything under a kilogram of room is as good as full
+        if free < 1. || loaded <= 0. {
+            *budget -= loaded / rate;
+            if free < 1. {
+                let name = self.get_object(ship).unwrap().name;
+                self.notify_fleet(ship, Notification::CargoFull { ship, name });
+            }
+            Ok(true)
+        } else {
+            *budget = 0.;
+            self.set_order_state(ship, OrderState::Working(stored / capacity.0));
+            Ok(false)
+        }
+    }
+
+    // moves all the ship's cargo into the holds of the target it's docked at
+    fn unload(&mut self, ship: ObjectHandle, target: ObjectHandle) -> Result<bool, OrderError> {
+        if !self.relation(ship).is_some_and(|relation| relation.is_connected()) {
+            return Err(OrderError::DockingRefused);
+        }
+        if self.get_object(target).and_then(|target| target.structure()).is_none_or(|s| s.capacity().0 <= 0.) {
+            return Err(OrderError::NoCargo);
+        }
+        let mut full = false;
+        for resource in Resource::ALL {
+            let carried = self.get_object(ship).unwrap().structure().map_or(Mass(0.), |s| s.stored(resource));
+            if carried.0 <= 0. {
+                continue;
+            }
+            let loaded = self.get_object_mut(target).unwrap().structure_mut().unwrap().load(resource, carried);
+            self.get_object_mut(ship).unwrap().structure_mut().unwrap().unload(resource, loaded);
+            full |= loaded.0 < carried.0;
+        }
+        if full { Err(OrderError::TargetFull) } else { Ok(true) }
+    }
+
+    // lands the orbiting ship at a site of the body, or hops there from another site
+    fn land(&mut self, ship: ObjectHandle, body: ObjectHandle, site: usize) -> Result<bool, OrderError> {
+        let location = self.sites(body).get(site).ok_or(OrderError::NoSite)?.location;
+        let hops = match self.relation(ship) {
+            Some(Relation::Landed { location: here }) if *here == location => return Ok(true),
+            Some(Relation::Landed { .. }) => 2.,
+            _ => 1.,
+        };
+        let delta_v = self.get_object(body).unwrap().landing_delta_v().ok_or(OrderError::NoSite)?;
+        self.burn(ship, delta_v * hops)?;
+        self.reparent(ship, body, Relation::Landed { location });
+        Ok(true)
+    }
+
+    // spends the fuel for `delta_v` km/s, if the ship has thrusters and enough of it
+    fn burn(&mut self, ship: ObjectHandle, delta_v: f64) -> Result<(), OrderError> {
+        let object = self.get_object(ship).unwrap();
+        let structure = object.structure().ok_or(OrderError::NoThrusters)?;
+        if structure.count(ComponentKind::Thruster) == 0 {
+            return Err(OrderError::NoThrusters);
+        }
+        let fuel = fuel_for(object.loaded_mass(), delta_v);
+        let before = structure.stored(Resource::Fuel);
+        if before.0 < fuel.0 {
+            return Err(OrderError::NoFuel);
+        }
+        self.get_object_mut(ship).unwrap().structure_mut().unwrap().unload(Resource::Fuel, fuel);
+        self.check_fuel(ship, before);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        change::Change,
+        object::{Location, ObjectBuilder, ObjectKind, ParentBuilder, Structure},
+    };
+
+    #[test]
+    fn transfer_to_carried_object_fails() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let pod = galaxy.spawn_object(
+            ObjectBuilder::default().name("Pod").kind(ObjectKind::Structure(Structure::new(vec![]))),
+            ParentBuilder::Relation(ship, Relation::Docked),
+        )
+        .unwrap();
+
+        for target in [ship, pod] {
+            assert_eq!(galaxy.transfer(ship, target, None, &mut 1.), Err(OrderError::OwnTarget));
+        }
+        assert!(!galaxy.ancestry(ship).contains(&pod));
+    }
+
+    #[test]
+    fn takeoff_burns_fuel_off_any_site() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let earth = galaxy.ancestry(ship)[1];
+        let moon = galaxy.find_child(earth, "Moon").unwrap();
+        // between the surveyed sites
+        galaxy.reparent(ship, moon, Relation::Landed { location: Location { latitude: 0.1, longitude: 0.2 } });
+        assert!(galaxy.landing_site(ship).is_none());
+
+        let fuel = |galaxy: &Galaxy| galaxy.get_object(ship).unwrap().structure().unwrap().stored(Resource::Fuel);
+        let before = fuel(&galaxy);
+        assert_eq!(galaxy.transfer(ship, earth, None, &mut 1.), Ok(false));
+        assert!(fuel(&galaxy).0 < before.0);
+    }
+
+    #[test]
+    fn transfer_stays_captured() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let sun = galaxy.ancestry(ship)[0];
+        let mars = galaxy.find_child(sun, "Mars").unwrap();
+        let depot = galaxy.find_child(mars, "Mars Depot").unwrap();
+        let subscription = galaxy.subscribe();
+
+        let mut captured = false;
+        for _ in 0..100_000 {
+            if galaxy.transfer(ship, depot, None, &mut 600.) == Ok(true) {
+                assert!(captured);
+                // out of Earth's sphere, into Mars's and up to the depot, without a move for every step
+                let moves = galaxy.read_changes(subscription).into_iter();
+                assert_eq!(moves.filter(|change| matches!(change, Change::Reparented { object, .. } if *object == ship)).count(), 3);
+                return;
+            }
+            let Parent::Relation(parent) = galaxy.get_object(ship).unwrap().parent else { unreachable!() };
+            if parent == mars {
+                let Some(Relation::Orbit(radius)) = galaxy.relation(ship) else { unreachable!() };
+                assert!((*radius as f64) < galaxy.soi(mars).unwrap().0, "left Mars at {radius}km");
+                captured = true;
+            } else {
+                assert!(!captured, "escaped Mars");
+            }
+        }
+        panic!("never arrived");
+    }
+
+    #[test]
+    fn window_waits_and_burns_its_delta_v() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let mars = galaxy.find_child(galaxy.ancestry(ship)[0], "Mars").unwrap();
+        galaxy.get_object_mut(ship).unwrap().structure_mut().unwrap().load(Resource::Fuel, Mass(600.));
+        // leaving soon, so the reactor doesn't run dry waiting
+        let plan = TransferPlan { departure: Time(galaxy.get_time().0 + 200_000.), ..galaxy.hohmann(ship, mars).unwrap() };
+        let fuel = |galaxy: &Galaxy| galaxy.get_object(ship).unwrap().structure().unwrap().stored(Resource::Fuel).0;
+        let burn = fuel_for(galaxy.get_object(ship).unwrap().loaded_mass(), plan.delta_v).0;
+        galaxy.get_orders_mut(ship).unwrap().orders.push(Order::Window(mars, plan));
+
+        // reactors burn a little fuel too, the transfer a lot at once
+        let dt = Time(21_600.);
+        let mut burnt_at = None;
+        while !galaxy.get_orders(ship).unwrap().is_done() {
+            let (now, before) = (galaxy.get_time().0, fuel(&galaxy));
+            assert!(now < plan.departure.0 + plan.duration.0 + dt.0, "{:?}", galaxy.get_orders(ship).unwrap().state);
+            galaxy.step(dt);
+            let spent = before - fuel(&galaxy);
+            if spent > burn / 2. {
+                assert!((spent - burn).abs() < burn * 0.05, "burnt {spent}kg instead of {burn}kg");
+                burnt_at = Some(now);
+            }
+        }
+        let burnt_at = burnt_at.unwrap();
+        assert!(burnt_at < plan.departure.0 && plan.departure.0 <= burnt_at + dt.0);
+        assert!(galaxy.get_time().0 >= plan.departure.0 + plan.duration.0);
+        assert!(matches!(galaxy.get_object(ship).unwrap().parent, Parent::Relation(parent) if parent == mars));
+    }
+
+    #[test]
+    fn window_fails_once_missed() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let mars = galaxy.find_child(galaxy.ancestry(ship)[0], "Mars").unwrap();
+        let plan = TransferPlan { departure: Time(galaxy.get_time().0 - 3_600.), ..galaxy.hohmann(ship, mars).unwrap() };
+        assert_eq!(galaxy.transfer(ship, mars, Some(plan), &mut 1.), Err(OrderError::MissedWindow));
+    }
+
+    #[test]
+    fn insert_at_current_starts_fresh() {
+        let mut queue = OrderQueue { orders: vec![Order::Mine, Order::Repeat], current: 1, state: OrderState::Working(0.5) };
+        queue.insert(0, Order::Mine);
+        assert_eq!((queue.current, queue.state.progress()), (2, Some(0.5)));
+        queue.insert(2, Order::Mine);
+        assert_eq!((queue.current, queue.state.progress()), (2, None));
+        assert!(matches!(queue.state, OrderState::Idle));
+    }
+
+    #[test]
+    fn stale_ships_have_no_orders() {
+        let mut galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        galaxy.destroy(ship);
+        assert!(galaxy.get_orders_mut(ship).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{
+    object::{Component, ComponentKind, ObjectHandle, Resource},
+    space::{Galaxy, Mass, Time, World},
+};
+
+/// power drawn by each component kind while enabled, in kW
+const THRUSTER_POWER: f64 = 300.;
+const DRILL_POWER: f64 = 400.;
+const SENSOR_POWER: f64 = 50.;
+const SHIPYARD_POWER: f64 = 500.;
+
+/// burns fuel from cargo to power its structure
+#[derive(Clone, Debug)]
+pub struct Reactor {
+    /// power at full load, in kW
+    pub output: f64,
+    /// fuel burned at full load, in kg/s
+    pub fuel_rate: f64,
+}
+
+impl Default for Reactor {
+    fn default() -> Self {
+        Self { output: 1_000., fuel_rate: 0.000_2 }
+    }
+}
+
+/// balance of a structure's grid, computed every step
+#[derive(Clone, Debug, Default)]
+pub struct PowerBudget {
+    /// output of the reactors, zero when out of fuel, in kW
+    pub generation: f64,
+    /// what every consumer asks for, in kW
+    pub demand: f64,
+    /// what the powered consumers draw, in kW
+    pub supplied: f64,
+    /// indices of the components left without power
+    pub shed: Vec<usize>,
+}
+
+impl Component {
+    /// power drawn while enabled, in kW
+    pub fn power_demand(&self) -> f64 {
+        match self {
+            Component::Thruster => THRUSTER_POWER,
+            Component::Drill => DRILL_POWER,
+            Component::Sensor(_) => SENSOR_POWER,
+            Component::Shipyard(_) => SHIPYARD_POWER,
+            Component::Refinery(refinery) => refinery.recipe().map_or(0., |recipe| recipe.power),
+            Component::Reactor(_) | Component::Cargo(_) | Component::Market(_) => 0.,
+        }
+    }
+}
+
+impl ComponentKind {
+    /// consumers with a lower priority are served first during brownouts
+    pub fn power_priority(&self) -> u8 {
+        match self {
+            ComponentKind::Sensor => 0,
+            ComponentKind::Thruster => 1,
+            ComponentKind::Drill => 2,
+            ComponentKind::Shipyard => 3,
+            ComponentKind::Refinery => 4,
+            ComponentKind::Reactor | ComponentKind::Cargo | ComponentKind::Market => u8::MAX,
+        }
+    }
+}
+
+impl PowerBudget {
+    pub fn is_brownout(&self) -> bool {
+        !self.shed.is_empty()
+    }
+}
+
+impl Galaxy {
+    /// balances the grid of every structure and burns the reactors' fuel for `dt`
+    pub(crate) fn run_power(&mut self, dt: Time) {
+        let structures: Vec<ObjectHandle> = self
+            .iter()
+            .filter(|(_, object)| object.structure().is_some())
+            .map(|(handle, _)| handle)
+            .collect();
+
+        for handle in structures {
+            let structure = self.get_object_mut(handle).unwrap().structure_mut().unwrap();
+
+            let (output, fuel_rate) = structure
+                .reactors()
+                .fold((0., 0.), |(output, rate), reactor| (output + reactor.output, rate + reactor.fuel_rate));
+            let generation = if structure.stored(Resource::Fuel).0 > 0. { output } else { 0. };
+
+            let mut consumers: Vec<(u8, usize, f64)> = structure
+                .components()
+                .iter()
+                .enumerate()
+                .map(|(i, component)| (component.kind().power_priority(), i, component.power_demand()))
+                .filter(|(_, _, demand)| *demand > 0.)
+                .collect();
+            consumers.sort_by_key(|(priority, i, _)| (*priority, *i));
+
+            let mut budget = PowerBudget { generation, ..Default::default() };
+            for (_, i, demand) in consumers {
+                budget.demand += demand;
+                if budget.supplied + demand <= generation {
+                    budget.supplied += demand;
+                } else {
+                    budget.shed.push(i);
+                }
+            }
+
+            let before = structure.stored(Resource::Fuel);
+            if output > 0. {
+                structure.unload(Resource::Fuel, Mass(budget.supplied / output * fuel_rate * dt.0));
+            }
+            structure.power = budget;
+            self.check_fuel(handle, before);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{
+    object::{ComponentKind, ObjectHandle, Resource, Structure},
+    space::{Galaxy, Mass, Time, World},
+};
+
+/// a batch process turning resources into other resources
+#[derive(Debug)]
+pub struct Recipe {
+    pub name: &'static str,
+    /// taken from cargo when a batch starts
+    pub inputs: &'static [(Resource, f64)],
+    /// put in cargo when a batch ends
+    pub outputs: &'static [(Resource, f64)],
+    pub duration: Time,
+    /// drawn from the structure's grid, in kW
+    pub power: f64,
+}
+
+pub const RECIPES: &[Recipe] = &[
+    Recipe {
+        name: "Ice melting",
+        inputs: &[(Resource::Ice, 100.)],
+        outputs: &[(Resource::Water, 100.)],
+        duration: Time(600.),
+        power: 200.,
+    },
+    Recipe {
+        name: "Electrolysis",
+        inputs: &[(Resource::Water, 100.)],
+        outputs: &[(Resource::Fuel, 90.)],
+        duration: Time(1_800.),
+        power: 800.,
+    },
+    Recipe {
+        name: "Hydrogen compression",
+        inputs: &[(Resource::Hydrogen, 100.)],
+        outputs: &[(Resource::Fuel, 95.)],
+        duration: Time(900.),
+        power: 300.,
+    },
+    Recipe {
+        name: "Smelting",
+        inputs: &[(Resource::Metals, 100.), (Resource::Rock, 50.)],
+        outputs: &[(Resource::Alloys, 80.)],
+        duration: Time(3_600.),
+        power: 600.,
+    },
+];
+
+/// runs a recipe in batches, out of and into its structure's cargo
+#[derive(Clone, Debug, Default)]
+pub struct Refinery {
+    /// index into [`RECIPES`]
+    pub recipe: Option<usize>,
+    /// time spent on the running batch
+    pub batch: Option<Time>,
+    /// why the refinery stopped, `None` while it runs
+    pub bottleneck: Option<Bottleneck>,
+    /// time spent running since the recipe was set
+    pub busy: Time,
+    /// time elapsed since the recipe was set
+    pub elapsed: Time,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bottleneck {
+    NoRecipe,
+    MissingInput(Resource),
+    OutputFull,
+    NoPower,
+}
+
+impl std::fmt::Display for Bottleneck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bottleneck::NoRecipe => write!(f, "no recipe"),
+            Bottleneck::MissingInput(resource) => write!(f, "missing {resource}"),
+            Bottleneck::OutputFull => write!(f, "cargo full"),
+            Bottleneck::NoPower => write!(f, "not enough power"),
+        }
+    }
+}
+
+impl Refinery {
+    pub fn new(recipe: usize) -> Self {
+        Self { recipe: Some(recipe), ..Default::default() }
+    }
+
+    pub fn recipe(&self) -> Option<&'static Recipe> {
+        self.recipe.map(|recipe| &RECIPES[recipe])
+    }
+
+    /// switches recipe, dropping the running batch
+    pub fn set_recipe(&mut self, recipe: Option<usize>) {
+        *self = Self { recipe, ..Default::default() };
+    }
+
+    /// fraction of the time spent running
+    pub fn utilization(&self) -> f64 {
+        if self.elapsed.0 > 0. { self.busy.0 / self.elapsed.0 } else { 0. }
+    }
+
+    /// average output in kg/s since the recipe was set
+    pub fn throughput(&self) -> Vec<(Resource, f64)> {
+        let Some(recipe) = self.recipe() else { return vec![] };
+        recipe
+            .outputs
+            .iter()
+            .map(|(resource, mass)| (*resource, mass / recipe.duration.0 * self.utilization()))
+            .collect()
+    }
+
+    // runs batches for `dt` if the grid powered the refinery
+    fn run(&mut self, structure: &mut Structure, powered: bool, dt: Time) {
+        self.elapsed.0 += dt.0;
+        let Some(recipe) = self.recipe() else {
+            self.bottleneck = Some(Bottleneck::NoRecipe);
+            return;
+        };
+        if self.batch.is_none()
+            && let Some(bottleneck) = Self::blocked(recipe, structure)
+        {
+            self.bottleneck = Some(bottleneck);
+            return;
+        }
+        if !powered {
+            self.bottleneck = Some(Bottleneck::NoPower);
+            return;
+        }
+        self.bottleneck = None;
+
+        let mut budget = dt.0;
+        while budget > 0. {
+            let elapsed = match self.batch {
+                Some(elapsed) => elapsed,
+                None => {
+                    if let Some(bottleneck) = Self::blocked(recipe, structure) {
+                        self.bottleneck = Some(bottleneck);
+                        return;
+                    }
+                    for (resource, mass) in recipe.inputs {
+                        structure.unload(*resource, Mass(*mass));
+                    }
+                    Time(0.)
+                }
+            };
+
+            let remaining = recipe.duration.0 - elapsed.0;
+            if budget < remaining {
+                self.batch = Some(Time(elapsed.0 + budget));
+                self.busy.0 += budget;
+                return;
+            }
+            budget -= remaining;
+            self.busy.0 += remaining;
+            self.batch = None;
+            for (resource, mass) in recipe.outputs {
+                structure.load(*resource, Mass(*mass));
+            }
+        }
+    }
+
+    // why a new batch can't start
+    fn blocked(recipe: &Recipe, structure: &Structure) -> Option<Bottleneck> {
+        if let Some((resource, _)) = recipe.inputs.iter().find(|(resource, mass)| structure.stored(*resource).0 < *mass) {
+            return Some(Bottleneck::MissingInput(*resource));
+        }
+        let consumed: f64 = recipe.inputs.iter().map(|(_, mass)| mass).sum();
+        let produced: f64 = recipe.outputs.iter().map(|(_, mass)| mass).sum();
+        if structure.free().0 + consumed < produced {
+            return Some(Bottleneck::OutputFull);
+        }
+        None
+    }
+}
+
+impl Galaxy {
+    /// advances every refinery by `dt`
+    pub(crate) fn run_refineries(&mut self, dt: Time) {
+        let plants: Vec<ObjectHandle> = self
+            .iter()
+            .filter(|(_, object)| object.structure().is_some_and(|structure| structure.count(ComponentKind::Refinery) > 0))
+            .map(|(handle, _)| handle)
+            .collect();
+
+        for plant in plants {
+            let structure = self.get_object_mut(plant).unwrap().structure_mut().unwrap();
+            for i in 0..structure.count(ComponentKind::Refinery) {
+                let mut refinery = structure.refineries().nth(i).unwrap().clone();
+                let powered = structure.is_powered(ComponentKind::Refinery, i);
+                refinery.run(structure, powered, dt);
+                *structure.refineries_mut().nth(i).unwrap() = refinery;
+            }
+        }
+    }
+}
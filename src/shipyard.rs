@@ -0,0 +1,297 @@
+use std::collections::VecDeque;
+
+use crate::{
+    faction::Owner,
+    message::Notification,
+    object::{Cargo, Component, ComponentKind, ObjectBuilder, ObjectHandle, ObjectKind, Parent, ParentBuilder, Relation, Resource, Sensor, Structure},
+    power::Reactor,
+    refinery::Refinery,
+    space::{Distance, Galaxy, Mass, Time, World},
+};
+
+/// something a shipyard knows how to build
+#[derive(Debug)]
+pub struct Blueprint {
+    pub name: &'static str,
+    /// resources taken from the station's cargo when the job starts
+    pub cost: &'static [(Resource, f64)],
+    pub duration: Time,
+    pub product: Product,
+}
+
+#[derive(Debug)]
+pub enum Product {
+    /// fitted to the job's target
+    Component(fn() -> Component),
+    /// docked at the station, joining the fleet when the player owns it
+    Ship { mass: Mass, components: fn() -> Vec<Component> },
+    /// launched into the station's orbit
+    Station { mass: Mass, components: fn() -> Vec<Component> },
+}
+
+pub const BLUEPRINTS: &[Blueprint] = &[
+    Blueprint {
+        name: "Cargo pod",
+        cost: &[(Resource::Metals, 500.)],
+        duration: Time(3_600.),
+        product: Product::Component(|| Component::Cargo(Cargo::new(Mass(5_000.)))),
+    },
+    Blueprint {
+        name: "Drill",
+        cost: &[(Resource::Metals, 800.), (Resource::Rock, 200.)],
+        duration: Time(7_200.),
+        product: Product::Component(|| Component::Drill),
+    },
+    Blueprint {
+        name: "Thruster",
+        cost: &[(Resource::Metals, 1_000.)],
+        duration: Time(7_200.),
+        product: Product::Component(|| Component::Thruster),
+    },
+    Blueprint {
+        name: "Sensor array",
+        cost: &[(Resource::Metals, 300.), (Resource::Ice, 50.)],
+        duration: Time(10_800.),
+        product: Product::Component(|| {
+            Component::Sensor(Sensor { range: Distance(500_000.), resolution: Distance(50_000.) })
+        }),
+    },
+    Blueprint {
+        name: "Reactor",
+        cost: &[(Resource::Metals, 1_500.), (Resource::Alloys, 200.)],
+        duration: Time(14_400.),
+        product: Product::Component(|| Component::Reactor(Reactor::default())),
+    },
+    Blueprint {
+        name: "Refinery",
+        cost: &[(Resource::Metals, 2_000.), (Resource::Rock, 500.)],
+        duration: Time(21_600.),
+        product: Product::Component(|| Component::Refinery(Refinery::default())),
+    },
+    Blueprint {
+        name: "Miner",
+        cost: &[(Resource::Metals, 3_000.), (Resource::Rock, 1_000.)],
+        duration: Time(86_400.),
+        product: Product::Ship {
+            mass: Mass(3_000.),
+            components: || {
+                vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Thruster,
+                    Component::Drill,
+                    Component::Cargo(Cargo::new(Mass(2_000.))),
+                    Component::Sensor(Sensor { range: Distance(100_000.), resolution: Distance(10_000.) }),
+                ]
+            },
+        },
+    },
+    Blueprint {
+        name: "Station",
+        cost: &[(Resource::Metals, 20_000.), (Resource::Rock, 20_000.)],
+        duration: Time(432_000.),
+        product: Product::Station {
+            mass: Mass(50_000.),
+            components: || {
+                vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(Cargo::new(Mass(100_000.))),
+                    Component::Shipyard(Shipyard::default()),
+                ]
+            },
+        },
+    },
+];
+
+/// builds blueprints one after the other, out of its structure's cargo
+#[derive(Clone, Debug, Default)]
+pub struct Shipyard {
+    pub queue: VecDeque<Job>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+    /// index into [`BLUEPRINTS`]
+    pub blueprint: usize,
+    /// structure receiving a built component, the station itself when `None`
+    pub target: Option<ObjectHandle>,
+    pub state: JobState,
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum JobState {
+    /// waiting for the station's cargo to cover the cost
+    #[default]
+    Waiting,
+    Building { elapsed: Time },
+}
+
+impl Job {
+    pub fn new(blueprint: usize, target: Option<ObjectHandle>) -> Self {
+        Self { blueprint, target, state: JobState::Waiting }
+    }
+
+    pub fn blueprint(&self) -> &'static Blueprint {
+        &BLUEPRINTS[self.blueprint]
+    }
+
+    /// completion from 0 to 1
+    pub fn progress(&self) -> f64 {
+        match self.state {
+            JobState::Waiting => 0.,
+            JobState::Building { elapsed } => (elapsed.0 / self.blueprint().duration.0).min(1.),
+        }
+    }
+}
+
+impl Blueprint {
+    /// resources missing from `structure` to start building
+    pub fn missing(&self, structure: &Structure) -> Vec<(Resource, Mass)> {
+        self.cost
+            .iter()
+            .map(|(resource, mass)| (*resource, Mass(mass - structure.stored(*resource).0)))
+            .filter(|(_, missing)| missing.0 > 0.)
+            .collect()
+    }
+}
+
+impl Galaxy {
+    /// advances the first job of every shipyard by `dt`
+    pub(crate) fn run_shipyards(&mut self, dt: Time) {
+        let stations: Vec<ObjectHandle> = self
+            .iter()
+            .filter(|(_, object)| object.structure().is_some_and(|structure| structure.shipyards().next().is_some()))
+            .map(|(handle, _)| handle)
+            .collect();
+
+        for station in stations {
+            let mut finished = vec![];
+            let yards = self.get_object(station).unwrap().structure().unwrap().count(ComponentKind::Shipyard);
+            for yard in 0..yards {
+                let structure = self.get_object_mut(station).unwrap().structure_mut().unwrap();
+                if !structure.is_powered(ComponentKind::Shipyard, yard) {
+                    continue;
+                }
+                let Some(job) = structure.shipyards().nth(yard).and_then(|shipyard| shipyard.queue.front()) else {
+                    continue;
+                };
+                let blueprint = job.blueprint();
+
+                let elapsed = match job.state {
+                    JobState::Building { elapsed } => elapsed,
+                    JobState::Waiting => {
+                        if !blueprint.missing(structure).is_empty() {
+                            continue;
+                        }
+                        for (resource, mass) in blueprint.cost {
+                            structure.unload(*resource, Mass(*mass));
+                        }
+                        Time(0.)
+                    }
+                };
+                let elapsed = Time(elapsed.0 + dt.0);
+
+                let shipyard = structure.shipyards_mut().nth(yard).unwrap();
+                if elapsed.0 >= blueprint.duration.0 {
+                    finished.push(shipyard.queue.pop_front().unwrap());
+                } else {
+                    shipyard.queue.front_mut().unwrap().state = JobState::Building { elapsed };
+                }
+            }
+
+            for job in finished {
+                self.deliver(station, job);
+            }
+        }
+    }
+
+    // hands the product over to the station's owner, giving back its cost if it can't be launched
+    fn deliver(&mut self, station: ObjectHandle, job: Job) {
+        let owner = self.get_object(station).unwrap().owner;
+        let delivered = match &job.blueprint().product {
+            Product::Component(component) => {
+                let target = job.target.filter(|target| self.get_object(*target).is_some()).unwrap_or(station);
+                if let Some(structure) = self.get_object_mut(target).unwrap().structure_mut() {
+                    structure.add_component(component());
+                }
+                true
+            }
+            Product::Ship { mass, components } => {
+                let ship = self.spawn_object(
+                    ObjectBuilder { owner, ..Default::default() }
+                        .name(job.blueprint().name)
+                        .mass(*mass)
+                        .kind(ObjectKind::Structure(Structure::new(components()))),
+                    ParentBuilder::Relation(station, Relation::Docked),
+                );
+                if let Ok(ship) = ship
+                    && owner == Some(Owner::Player)
+                {
+                    self.add_to_fleet(ship);
+                }
+                ship.is_ok()
+            }
+            Product::Station { mass, components } => {
+                self.launch(station, owner, job.blueprint().name, *mass, components()).is_some()
+            }
+        };
+        if !delivered {
+            let structure = self.get_object_mut(station).unwrap().structure_mut().unwrap();
+            for (resource, mass) in job.blueprint().cost {
+                structure.load(*resource, Mass(*mass));
+            }
+            if owner == Some(Owner::Player) {
+                self.notify(Notification::LaunchFailed { station, product: job.blueprint().name });
+            }
+        }
+    }
+
+    // spawns a new structure sharing the station's orbit, or its site when landed
+    fn launch(
+        &mut self,
+        station: ObjectHandle,
+        owner: Option<Owner>,
+        name: &'static str,
+        mass: Mass,
+        components: Vec<Component>,
+    ) -> Option<ObjectHandle> {
+        let Parent::Relation(parent) = self.get_object(station)?.parent else {
+            return None;
+        };
+        let relation = self.get_object(parent)?.get_child(station)?.clone();
+        self.spawn_object(
+            ObjectBuilder { owner, ..Default::default() }
+                .name(name)
+                .mass(mass)
+                .kind(ObjectKind::Structure(Structure::new(components))),
+            ParentBuilder::Relation(parent, relation),
+        )
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_yard_builds_ships_for_the_fleet() {
+        let mut galaxy = Galaxy::new();
+        let earth = galaxy.ancestry(galaxy.fleet()[0])[1];
+        let yard = galaxy.find_child(earth, "Orbital Yard").unwrap();
+        assert_eq!(galaxy.get_object(yard).unwrap().owner, Some(Owner::Player));
+
+        let miner = BLUEPRINTS.iter().position(|blueprint| blueprint.name == "Miner").unwrap();
+        let structure = galaxy.get_object_mut(yard).unwrap().structure_mut().unwrap();
+        structure.shipyards_mut().next().unwrap().queue.push_back(Job::new(miner, None));
+
+        let fleet = galaxy.fleet().len();
+        for _ in 0..200 {
+            galaxy.step(Time(600.));
+        }
+        assert_eq!(galaxy.fleet().len(), fleet + 1);
+        let ship = *galaxy.fleet().last().unwrap();
+        let ship = galaxy.get_object(ship).unwrap();
+        assert_eq!((ship.name, ship.owner), ("Miner", Some(Owner::Player)));
+        assert!(matches!(ship.parent, Parent::Relation(parent) if parent == yard));
+    }
+}
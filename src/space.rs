@@ -1,29 +1,71 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 use rstar::{RTree, primitives::GeomWithData};
-use slotmap::SlotMap;
+use slotmap::{SecondaryMap, SlotMap};
 
-use crate::object::{Body, Composition, Object, ObjectBuilder, ObjectHandle, ObjectKind, Parent, ParentBuilder, Relation};
+use crate::{
+    bookmark::Bookmark,
+    change::{Change, ChangeLog, Subscription},
+    collision::OrbitError,
+    faction::{FactionId, Factions, Owner, Role, Standing},
+    knowledge::{Contact, Knowledge},
+    market::Market,
+    message::{Message, Notification},
+    object::{
+        Body, Cargo, Component, ComponentKind, Composition, Field, FieldMorphology, Location, Object, ObjectBuilder,
+        ObjectHandle, ObjectKind, Parent, ParentBuilder, Relation, Resource, Sensor, Structure,
+    },
+    orders::OrderQueue,
+    power::Reactor,
+    refinery::Refinery,
+    shipyard::Shipyard,
+    surface::{Site, Terrain},
+};
 
 
 pub type Position = [f32; 2];
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Mass(pub f64);
 #[derive(Clone, Copy, Debug)]
 pub struct Distance(pub f64);
+/// simulation time in seconds
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Time(pub f64);
 
 
 // interface from ratatui app to a game world
 pub trait World {
-    fn spawn_object(&mut self, object: ObjectBuilder, parent: ParentBuilder) -> ObjectHandle;
+    /// spawns the object and its children, unless a relation to the parent is invalid
+    fn spawn_object(&mut self, object: ObjectBuilder, parent: ParentBuilder) -> Result<ObjectHandle, OrbitError>;
     fn get_object(&self, object_handle: ObjectHandle) -> Option<&Object>;
+    fn get_object_mut(&mut self, object_handle: ObjectHandle) -> Option<&mut Object>;
+    /// the player's currently controlled ship
     fn get_handle(&self) -> Option<ObjectHandle>;
+    /// switches control to another ship of the fleet
+    fn set_handle(&mut self, object_handle: ObjectHandle);
+    /// every ship owned by the player
+    fn fleet(&self) -> &[ObjectHandle];
+    fn get_orders(&self, object_handle: ObjectHandle) -> Option<&OrderQueue>;
+    /// `None` once the object is gone
+    fn get_orders_mut(&mut self, object_handle: ObjectHandle) -> Option<&mut OrderQueue>;
+    /// the player's money
+    fn credits(&self) -> f64;
+    fn credits_mut(&mut self) -> &mut f64;
+    /// what the player has detected so far
+    fn knowledge(&self) -> &Knowledge;
+    fn get_time(&self) -> Time;
+    /// advances the simulation by `dt`
+    fn step(&mut self, dt: Time);
 }
 
 impl Display for Mass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}kg", self.0)
+        if self.0.abs() < 1e6 {
+            write!(f, "{:.0}kg", self.0)
+        } else {
+            write!(f, "{:.3e}kg", self.0)
+        }
     }
 }
 impl Display for Distance {
@@ -31,56 +73,619 @@ impl Display for Distance {
         write!(f, "{}km", self.0)
     }
 }
+impl Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.0.max(0.) as u64;
+        let (days, hours, minutes, seconds) =
+            (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+        if days > 0 {
+            write!(f, "{days}d {hours}h")
+        } else if hours > 0 {
+            write!(f, "{hours}h {minutes}m")
+        } else if minutes > 0 {
+            write!(f, "{minutes}m {seconds}s")
+        } else {
+            write!(f, "{seconds}s")
+        }
+    }
+}
 
 /// galaxies store clusters of systems and objects.
 #[derive(Debug)]
 pub struct Galaxy {
     spatial: RTree<GeomWithData<Position, ObjectHandle>>,
     objects: SlotMap<ObjectHandle, Object>,
-    handle: Option<ObjectHandle>,
+    fleet: Vec<ObjectHandle>,
+    active: usize,
+    orders: SecondaryMap<ObjectHandle, OrderQueue>,
+    /// surface sites of the bodies that have a surface
+    surfaces: SecondaryMap<ObjectHandle, Vec<Site>>,
+    credits: f64,
+    factions: Factions,
+    knowledge: Knowledge,
+    /// notifications raised since the app last took them
+    notifications: Vec<Message>,
+    /// objects spawned, despawned, moved or changing mass, for whoever follows them
+    changes: ChangeLog,
+    /// places the player marked, dropped along with their object
+    bookmarks: Vec<Bookmark>,
+    time: Time,
 }
 
+/// seed of the demo world
+const DEFAULT_SEED: u64 = 0x5eed;
+
 impl Default for Galaxy {
     fn default() -> Self {
-        Self { spatial: RTree::new(), objects: SlotMap::with_key(), handle: None }
+        Self {
+            spatial: RTree::new(),
+            objects: SlotMap::with_key(),
+            fleet: vec![],
+            active: 0,
+            orders: SecondaryMap::new(),
+            surfaces: SecondaryMap::new(),
+            credits: 0.,
+            factions: Factions::new(0),
+            knowledge: Knowledge::default(),
+            notifications: vec![],
+            changes: ChangeLog::default(),
+            bookmarks: vec![],
+            time: Time::default(),
+        }
     }
 }
 
 impl Galaxy {
     pub fn new() -> Self {
-        let mut galaxy = Galaxy::default();
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// the demo world, with its AI driven by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        let mut galaxy = Galaxy { factions: Factions::new(seed), ..Default::default() };
 
         let sun = galaxy.spawn_object(
-            ObjectBuilder::default().name("Sun").mass(Mass(1_000_000.)).kind(ObjectKind::Body(Body { composition: Composition::default(), radius: Distance(100.) })),
+            ObjectBuilder::default()
+                .name("Sun")
+                .mass(Mass(1.989e30))
+                .kind(ObjectKind::Body(Body {
+                    composition: Composition::new(0.73, 0.25, 0., 0., 0.02).unwrap(),
+                    radius: Distance(696_000.),
+                }))
+                .child(
+                    ObjectBuilder::default()
+                        .name("Mercury")
+                        .mass(Mass(3.301e23))
+                        .kind(ObjectKind::Body(Body {
+                            composition: Composition::new(0., 0., 0.3, 0., 0.7).unwrap(),
+                            radius: Distance(2_440.),
+                        })),
+                    Relation::Orbit(57_909_000),
+                )
+                .child(
+                    ObjectBuilder::default()
+                        .name("Earth")
+                        .mass(Mass(5.972e24))
+                        .kind(ObjectKind::Body(Body {
+                            composition: Composition::new(0., 0., 0.67, 0.01, 0.32).unwrap(),
+                            radius: Distance(6_371.),
+                        }))
+                        .child(
+                            ObjectBuilder::default()
+                                .name("Moon")
+                                .mass(Mass(7.342e22))
+                                .kind(ObjectKind::Body(Body {
+                                    composition: Composition::new(0., 0., 0.9, 0.02, 0.08).unwrap(),
+                                    radius: Distance(1_737.),
+                                })),
+                            Relation::Orbit(384_400),
+                        ),
+                    Relation::Orbit(149_598_000),
+                )
+                .child(
+                    ObjectBuilder::default()
+                        .name("Mars")
+                        .mass(Mass(6.417e23))
+                        .kind(ObjectKind::Body(Body {
+                            composition: Composition::new(0., 0., 0.75, 0.05, 0.2).unwrap(),
+                            radius: Distance(3_390.),
+                        })),
+                    Relation::Orbit(227_939_000),
+                )
+                .child(
+                    ObjectBuilder::default()
+                        .name("Main Belt")
+                        .mass(Mass(2.39e21))
+                        .kind(ObjectKind::Field(Field {
+                            composition: Composition::new(0., 0., 0.75, 0.1, 0.15).unwrap(),
+                            morphology: FieldMorphology::Belt {
+                                inner: Distance(329_000_000.),
+                                outer: Distance(479_000_000.),
+                            },
+                        })),
+                    Relation::Orbit(404_000_000),
+                ),
             ParentBuilder::Position([0.3, 0.2]),
-        );
+        ).expect("the demo world is valid");
+        let earth = galaxy.find_child(sun, "Earth").unwrap();
+        let mut fuel_tank = Cargo::new(Mass(1_000.));
+        fuel_tank.load(Resource::Fuel, Mass(300.));
         let ship = galaxy.spawn_object(
-            ObjectBuilder::default().name("Ship").mass(Mass(2_000.)),
-            ParentBuilder::Relation(sun, Relation::Orbit(300)),
-        );
-        galaxy.handle = Some(ship);
-        
+            ObjectBuilder::default()
+                .name("Ship")
+                .mass(Mass(2_000.))
+                .owner(Owner::Player)
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Thruster,
+                    Component::Drill,
+                    Component::Cargo(fuel_tank),
+                    Component::Sensor(Sensor {
+                        range: Distance(1_000_000.),
+                        resolution: Distance(100_000.),
+                    }),
+                ]))),
+            ParentBuilder::Relation(earth, Relation::Orbit(7_000)),
+        ).expect("the demo world is valid");
+        let moon = galaxy.find_child(earth, "Moon").unwrap();
+        let mut hold = Cargo::new(Mass(20_000.));
+        hold.load(Resource::Rock, Mass(6_000.));
+        hold.load(Resource::Ice, Mass(1_500.));
+        hold.load(Resource::Fuel, Mass(2_000.));
+        let hauler = galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Hauler")
+                .mass(Mass(12_000.))
+                .owner(Owner::Player)
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Thruster,
+                    Component::Thruster,
+                    Component::Cargo(hold),
+                    Component::Sensor(Sensor {
+                        range: Distance(200_000.),
+                        resolution: Distance(20_000.),
+                    }),
+                ]))),
+            ParentBuilder::Relation(moon, Relation::Orbit(2_500)),
+        ).expect("the demo world is valid");
+        let mut stock = Cargo::new(Mass(100_000.));
+        stock.load(Resource::Metals, Mass(12_000.));
+        stock.load(Resource::Rock, Mass(8_000.));
+        stock.load(Resource::Ice, Mass(2_000.));
+        stock.load(Resource::Fuel, Mass(500.));
+        let earth_station = galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Earth Station")
+                .mass(Mass(80_000.))
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(stock),
+                    Component::Shipyard(Shipyard::default()),
+                    Component::Refinery(Refinery::new(0)),
+                    Component::Refinery(Refinery::new(1)),
+                    Component::Refinery(Refinery::new(3)),
+                    Component::Market(Market::default()),
+                    Component::Sensor(Sensor {
+                        range: Distance(500_000.),
+                        resolution: Distance(50_000.),
+                    }),
+                ]))),
+            ParentBuilder::Relation(earth, Relation::Orbit(6_771)),
+        ).expect("the demo world is valid");
+        // the player's own yard, where they build their fleet up
+        let mut stock = Cargo::new(Mass(40_000.));
+        stock.load(Resource::Metals, Mass(6_000.));
+        stock.load(Resource::Rock, Mass(2_000.));
+        stock.load(Resource::Fuel, Mass(2_000.));
+        galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Orbital Yard")
+                .mass(Mass(30_000.))
+                .owner(Owner::Player)
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(stock),
+                    Component::Shipyard(Shipyard::default()),
+                ]))),
+            ParentBuilder::Relation(earth, Relation::Orbit(42_164)),
+        ).expect("the demo world is valid");
+        let mars = galaxy.find_child(sun, "Mars").unwrap();
+        let mut stock = Cargo::new(Mass(60_000.));
+        stock.load(Resource::Ice, Mass(15_000.));
+        stock.load(Resource::Water, Mass(4_000.));
+        stock.load(Resource::Metals, Mass(800.));
+        stock.load(Resource::Fuel, Mass(2_000.));
+        let mars_depot = galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Mars Depot")
+                .mass(Mass(40_000.))
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(stock),
+                    Component::Refinery(Refinery::new(0)),
+                    Component::Market(Market { spread: 0.15 }),
+                    Component::Sensor(Sensor {
+                        range: Distance(500_000.),
+                        resolution: Distance(50_000.),
+                    }),
+                ]))),
+            ParentBuilder::Relation(mars, Relation::Orbit(20_000)),
+        ).expect("the demo world is valid");
+
+        let terran = galaxy.factions.add("Terran Authority", 200_000.);
+        let union = galaxy.factions.add("Belt Union", 50_000.);
+        let mut tank = Cargo::new(Mass(30_000.));
+        tank.load(Resource::Fuel, Mass(6_000.));
+        galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Fuel Module")
+                .mass(Mass(8_000.))
+                .owner(Owner::Faction(terran))
+                .kind(ObjectKind::Structure(Structure::new(vec![Component::Cargo(tank)]))),
+            ParentBuilder::Relation(mars_depot, Relation::Attached),
+        ).expect("the demo world is valid");
+        let ice_cap = galaxy.sites(moon).iter().find(|site| site.terrain == Terrain::IceCap).unwrap().location;
+        let mut stock = Cargo::new(Mass(40_000.));
+        stock.load(Resource::Ice, Mass(10_000.));
+        stock.load(Resource::Fuel, Mass(1_000.));
+        galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Moon Base")
+                .mass(Mass(60_000.))
+                .owner(Owner::Faction(terran))
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(stock),
+                    Component::Shipyard(Shipyard::default()),
+                    Component::Refinery(Refinery::new(0)),
+                ]))),
+            ParentBuilder::Relation(moon, Relation::Landed { location: ice_cap }),
+        ).expect("the demo world is valid");
+        galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Eagle")
+                .mass(Mass(2_200.))
+                .kind(ObjectKind::Structure(Structure::new(vec![Component::Cargo(Cargo::new(Mass(50.)))]))),
+            ParentBuilder::Relation(moon, Relation::Landed { location: Location { latitude: 0.67, longitude: 23.47 } }),
+        ).expect("the demo world is valid");
+
+        let belt = galaxy.find_child(sun, "Main Belt").unwrap();
+        let mut stock = Cargo::new(Mass(80_000.));
+        stock.load(Resource::Ice, Mass(6_000.));
+        stock.load(Resource::Fuel, Mass(3_000.));
+        let ceres_outpost = galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Ceres Outpost")
+                .mass(Mass(50_000.))
+                .kind(ObjectKind::Structure(Structure::new(vec![
+                    Component::Reactor(Reactor::default()),
+                    Component::Reactor(Reactor::default()),
+                    Component::Cargo(stock),
+                    Component::Refinery(Refinery::new(0)),
+                    Component::Refinery(Refinery::new(1)),
+                    Component::Refinery(Refinery::new(3)),
+                    Component::Market(Market { spread: 0.2 }),
+                    Component::Sensor(Sensor {
+                        range: Distance(500_000.),
+                        resolution: Distance(50_000.),
+                    }),
+                ]))),
+            ParentBuilder::Relation(belt, Relation::Orbit(40_000)),
+        ).expect("the demo world is valid");
+
+        for (station, faction) in [(earth_station, terran), (mars_depot, terran), (ceres_outpost, union)] {
+            galaxy.objects[station].owner = Some(Owner::Faction(faction));
+        }
+        galaxy.factions.set_standing(Owner::Player, Owner::Faction(terran), Standing::Allied);
+        galaxy.factions.set_standing(Owner::Faction(terran), Owner::Faction(union), Standing::Hostile);
+
+        let patrol = galaxy.spawn_npc("Patrol", terran, 2, false, Mass(0.), earth_station).expect("the demo world is valid");
+        galaxy.factions.assign(patrol, terran, Role::Patrol, earth_station);
+        for name in ["Prospector", "Digger"] {
+            let miner = galaxy.spawn_npc(name, union, 1, true, Mass(5_000.), ceres_outpost).expect("the demo world is valid");
+            galaxy.factions.assign(miner, union, Role::Miner, ceres_outpost);
+        }
+        let freighter = galaxy.spawn_npc("Freighter", union, 2, false, Mass(20_000.), ceres_outpost).expect("the demo world is valid");
+        galaxy.factions.assign(freighter, union, Role::Hauler, ceres_outpost);
+
+        galaxy.fleet = vec![ship, hauler];
+        galaxy.credits = 10_000.;
+        galaxy.sweep_sensors();
+
         galaxy
     }
 
-    pub fn new_cluster(stars: u32) -> Self {
+    // spawns an AI ship in orbit of its home station
+    fn spawn_npc(
+        &mut self,
+        name: &'static str,
+        faction: FactionId,
+        thrusters: usize,
+        drill: bool,
+        hold: Mass,
+        home: ObjectHandle,
+    ) -> Result<ObjectHandle, OrbitError> {
+        let mut tank = Cargo::new(Mass(500.));
+        tank.load(Resource::Fuel, Mass(300.));
+        let mut components = vec![Component::Reactor(Reactor::default()), Component::Cargo(tank)];
+        components.extend((0..thrusters).map(|_| Component::Thruster));
+        if drill {
+            components.push(Component::Drill);
+        }
+        if hold.0 > 0. {
+            components.push(Component::Cargo(Cargo::new(hold)));
+        }
+        self.spawn_object(
+            ObjectBuilder::default()
+                .name(name)
+                .mass(Mass(3_000. + hold.0 / 4.))
+                .owner(Owner::Faction(faction))
+                .kind(ObjectKind::Structure(Structure::new(components))),
+            ParentBuilder::Relation(home, Relation::Docked),
+        )
+    }
+
+    pub fn new_cluster(_stars: u32) -> Self {
         todo!()
     }
 
+    pub fn find_child(&self, object_handle: ObjectHandle, name: &str) -> Option<ObjectHandle> {
+        self.objects
+            .get(object_handle)?
+            .children
+            .as_ref()?
+            .keys()
+            .find(|child| self.objects[**child].name == name)
+            .copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectHandle, &Object)> {
+        self.objects.iter()
+    }
+
+    /// surface sites of a body, empty for anything without a solid surface
+    pub fn sites(&self, object_handle: ObjectHandle) -> &[Site] {
+        self.surfaces.get(object_handle).map_or(&[], |sites| sites.as_slice())
+    }
+
+    pub fn sites_mut(&mut self, object_handle: ObjectHandle) -> &mut [Site] {
+        self.surfaces.get_mut(object_handle).map_or(&mut [], |sites| sites.as_mut_slice())
+    }
+
+    pub fn factions(&self) -> &Factions {
+        &self.factions
+    }
+
+    pub fn factions_mut(&mut self) -> &mut Factions {
+        &mut self.factions
+    }
+
+    /// tells the player about something, once the step is over
+    pub fn notify(&mut self, notification: Notification) {
+        self.notifications.push(Message { time: self.time, notification });
+    }
+
+    /// notifications raised since the last call, oldest first
+    pub fn take_notifications(&mut self) -> Vec<Message> {
+        std::mem::take(&mut self.notifications)
+    }
+
+    /// starts following the changes made to objects from now on
+    pub fn subscribe(&mut self) -> Subscription {
+        self.changes.subscribe()
+    }
+
+    /// changes made since the subscriber last read them, oldest first
+    pub fn read_changes(&mut self, subscription: Subscription) -> Vec<Change> {
+        self.changes.read(subscription)
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn bookmarks_mut(&mut self) -> &mut Vec<Bookmark> {
+        &mut self.bookmarks
+    }
+
+    /// sets the mass of an object, such as a body being mined
+    pub fn set_mass(&mut self, object_handle: ObjectHandle, mass: Mass) {
+        let Some(object) = self.objects.get_mut(object_handle) else { return };
+        let from = std::mem::replace(&mut object.mass, mass);
+        self.changes.push(Change::MassChanged { object: object_handle, from, to: mass });
+    }
+
+    pub fn add_to_fleet(&mut self, object_handle: ObjectHandle) {
+        if !self.fleet.contains(&object_handle) {
+            self.fleet.push(object_handle);
+        }
+    }
+
+    /// removes the object from the world along with everything docked, landed or orbiting it
+    pub fn destroy(&mut self, object_handle: ObjectHandle) {
+        let Some(object) = self.objects.get(object_handle) else { return };
+        let children: Vec<ObjectHandle> = object.children.iter().flatten().map(|(child, _)| *child).collect();
+        for child in children {
+            self.destroy(child);
+        }
+
+        match self.objects[object_handle].parent {
+            Parent::Relation(parent) => {
+                self.objects[parent].remove_child(object_handle);
+            }
+            Parent::Position(pos) => {
+                self.spatial.remove(&GeomWithData::new(pos, object_handle));
+            }
+        }
+        self.objects.remove(object_handle);
+        self.changes.push(Change::Despawned(object_handle));
+        self.orders.remove(object_handle);
+        self.surfaces.remove(object_handle);
+        self.factions.dismiss(object_handle);
+        self.bookmarks.retain(|bookmark| bookmark.object != object_handle);
+        self.knowledge.forget(object_handle);
+
+        let active = self.get_handle();
+        self.fleet.retain(|ship| *ship != object_handle);
+        self.active = active.and_then(|active| self.fleet.iter().position(|ship| *ship == active)).unwrap_or(0);
+    }
+
+    /// moves a child object under a new parent
+    pub fn reparent(&mut self, object_handle: ObjectHandle, parent_handle: ObjectHandle, relation: Relation) {
+        debug_assert!(!self.ancestry(parent_handle).contains(&object_handle), "reparenting would make a cycle");
+        let from = match self.objects[object_handle].parent {
+            Parent::Relation(old_parent) => {
+                self.objects[old_parent].remove_child(object_handle);
+                Some(old_parent)
+            }
+            Parent::Position(pos) => {
+                self.spatial.remove(&GeomWithData::new(pos, object_handle));
+                None
+            }
+        };
+        self.objects[object_handle].parent = Parent::Relation(parent_handle);
+        self.objects[parent_handle].insert_child(object_handle, relation);
+        self.changes.push(Change::Reparented { object: object_handle, from, to: parent_handle });
+    }
+
+    /// how the object relates to its parent, `None` for roots
+    pub fn relation(&self, object_handle: ObjectHandle) -> Option<&Relation> {
+        let Parent::Relation(parent_handle) = self.objects.get(object_handle)?.parent else {
+            return None;
+        };
+        self.objects[parent_handle].get_child(object_handle)
+    }
+
+    /// relation taken by a ship arriving at an object. structures let ships dock unless their
+    /// owners are hostile.
+    pub fn parking_orbit(&self, ship: ObjectHandle, object_handle: ObjectHandle) -> Relation {
+        match &self.objects[object_handle].kind {
+            ObjectKind::Body(body) => Relation::Orbit((body.radius.0 * 1.5) as usize),
+            ObjectKind::Field(_) => Relation::Orbit(1_000),
+            ObjectKind::Structure(_) if self.standing(ship, object_handle) == Standing::Hostile => Relation::Orbit(10),
+            ObjectKind::Structure(_) => Relation::Docked,
+        }
+    }
+
+    /// the object's parents from the root down to the object itself
+    pub fn ancestry(&self, object_handle: ObjectHandle) -> Vec<ObjectHandle> {
+        let mut ancestry: Vec<_> = self.lineage(object_handle).into_iter().map(|(handle, _)| handle).collect();
+        ancestry.reverse();
+        ancestry
+    }
+
+    /// the object followed by its parents up to the root, with the distance from the object to
+    /// each of them along the hierarchy
+    fn lineage(&self, object_handle: ObjectHandle) -> Vec<(ObjectHandle, Distance)> {
+        let mut lineage = vec![(object_handle, Distance(0.))];
+        let mut distance = 0.;
+        let mut current = object_handle;
+        while let Some(Parent::Relation(parent_handle)) = self.objects.get(current).map(|o| &o.parent) {
+            let parent = &self.objects[*parent_handle];
+            distance += match parent.get_child(current) {
+                Some(Relation::Landed { .. }) => parent.radius().map_or(0., |radius| radius.0),
+                Some(relation) => relation.distance().0,
+                None => 0.,
+            };
+            lineage.push((*parent_handle, Distance(distance)));
+            current = *parent_handle;
+        }
+        lineage
+    }
+
+    /// upper bound of the distance between two objects, following the hierarchy through their
+    /// closest common parent. `None` when they don't share a root.
+    pub fn distance(&self, a: ObjectHandle, b: ObjectHandle) -> Option<Distance> {
+        let lineage_b = self.lineage(b);
+        self.lineage(a).into_iter().find_map(|(handle, distance_a)| {
+            lineage_b
+                .iter()
+                .find(|(other, _)| *other == handle)
+                .map(|(_, distance_b)| Distance(distance_a.0 + distance_b.0))
+        })
+    }
+
+    // refreshes the player's knowledge from the sensors of their fleet
+    fn sweep_sensors(&mut self) {
+        let mut readings = vec![];
+        // every object's way up to the root, worked out once for all the ships this step
+        let lineages: SecondaryMap<ObjectHandle, Vec<(ObjectHandle, Distance)>> =
+            self.objects.keys().map(|object_handle| (object_handle, self.lineage(object_handle))).collect();
+
+        for &ship in &self.fleet {
+            // ships always know themselves and what they orbit
+            for &(object_handle, _) in &lineages[ship] {
+                let exact = object_handle == ship;
+                readings.push((object_handle, self.reading(object_handle, if exact { 0. } else { 0.5 }, exact)));
+            }
+
+            let ObjectKind::Structure(structure) = &self.objects[ship].kind else { continue };
+            let sensors: Vec<_> = structure
+                .sensors()
+                .enumerate()
+                .filter(|(nth, _)| structure.is_powered(ComponentKind::Sensor, *nth))
+                .map(|(_, sensor)| sensor)
+                .collect();
+            if sensors.is_empty() {
+                continue;
+            }
+            let above: HashMap<ObjectHandle, f64> = lineages[ship].iter().map(|(handle, distance)| (*handle, distance.0)).collect();
+            for (object_handle, lineage) in &lineages {
+                // the first of its parents the ship shares is the closest
+                let Some(distance) = lineage.iter().find_map(|(handle, distance)| Some(distance.0 + above.get(handle)?)) else {
+                    continue;
+                };
+                for sensor in sensors.iter().filter(|sensor| distance <= sensor.range.0) {
+                    let error = 0.5 * distance / sensor.range.0;
+                    let resolved = distance <= sensor.resolution.0;
+                    readings.push((object_handle, self.reading(object_handle, error, resolved)));
+                }
+            }
+        }
+
+        for (object_handle, contact) in readings {
+            self.knowledge.observe(object_handle, contact);
+        }
+    }
+
+    fn reading(&self, object_handle: ObjectHandle, mass_error: f64, resolved: bool) -> Contact {
+        let object = &self.objects[object_handle];
+        Contact {
+            seen: self.time,
+            mass: object.mass,
+            mass_error,
+            composition: if resolved { object.composition().cloned() } else { None },
+            prices: match object.structure().and_then(|structure| Some((structure, structure.market()?))) {
+                Some((structure, market)) => Resource::ALL
+                    .iter()
+                    .map(|resource| (*resource, market.ask(structure, *resource), market.bid(structure, *resource)))
+                    .collect(),
+                None => vec![],
+            },
+        }
+    }
+
     // recursively spawns children from spawn_object
     fn spawn_children(
         &mut self,
         object_handle: ObjectHandle,
         children: Vec<(ObjectBuilder, Relation)>,
-    ) {
+    ) -> Result<(), OrbitError> {
         for (object, relation) in children {
-            self.spawn_object(object, ParentBuilder::Relation(object_handle, relation));
+            self.spawn_object(object, ParentBuilder::Relation(object_handle, relation))?;
         }
+        Ok(())
     }
 }
 
 impl World for Galaxy {
-    fn spawn_object(&mut self, object: ObjectBuilder, parent: ParentBuilder) -> ObjectHandle {
+    fn spawn_object(&mut self, object: ObjectBuilder, parent: ParentBuilder) -> Result<ObjectHandle, OrbitError> {
+        if let ParentBuilder::Relation(parent_handle, relation) = &parent {
+            self.validate_orbit(*parent_handle, relation)?;
+        }
+
         let object_handle = self.objects.insert(Object {
             parent: match parent {
                 ParentBuilder::Position(pos) => Parent::Position(pos),
@@ -91,8 +696,10 @@ impl World for Galaxy {
             children: None,
             mass: object.mass.unwrap_or(Mass(1_000.)),
             name: object.name.unwrap_or("Body"),
+            owner: object.owner,
             kind: object.kind.unwrap_or(ObjectKind::Body(Body { composition: Composition::default(), radius: Distance(300.) })),
         });
+        self.changes.push(Change::Spawned(object_handle));
 
         match parent {
             ParentBuilder::Position(pos) => {
@@ -106,18 +713,118 @@ impl World for Galaxy {
             }
         }
 
-        if let Some(children) = object.children {
-            self.spawn_children(object_handle, children);
+        if let ObjectKind::Body(body) = &self.objects[object_handle].kind {
+            let sites = Site::survey(&body.composition, body.radius, self.objects[object_handle].mass);
+            if !sites.is_empty() {
+                self.surfaces.insert(object_handle, sites);
+            }
+        }
+
+        // a child that doesn't fit takes the whole object down with it
+        if let Some(children) = object.children
+            && let Err(error) = self.spawn_children(object_handle, children)
+        {
+            self.destroy(object_handle);
+            return Err(error);
         }
 
-        object_handle
+        Ok(object_handle)
     }
 
     fn get_object(&self, object_handle: ObjectHandle) -> Option<&Object> {
         self.objects.get(object_handle)
     }
 
+    fn get_object_mut(&mut self, object_handle: ObjectHandle) -> Option<&mut Object> {
+        self.objects.get_mut(object_handle)
+    }
+
     fn get_handle(&self) -> Option<ObjectHandle> {
-        self.handle
+        self.fleet.get(self.active).copied()
+    }
+
+    fn set_handle(&mut self, object_handle: ObjectHandle) {
+        if let Some(index) = self.fleet.iter().position(|ship| *ship == object_handle) {
+            self.active = index;
+        }
+    }
+
+    fn fleet(&self) -> &[ObjectHandle] {
+        &self.fleet
+    }
+
+    fn get_orders(&self, object_handle: ObjectHandle) -> Option<&OrderQueue> {
+        self.orders.get(object_handle)
+    }
+
+    fn get_orders_mut(&mut self, object_handle: ObjectHandle) -> Option<&mut OrderQueue> {
+        // the orders map can't tell a stale handle from a fresh one on its own
+        if !self.objects.contains_key(object_handle) {
+            return None;
+        }
+        Some(self.orders.entry(object_handle)?.or_default())
+    }
+
+    fn credits(&self) -> f64 {
+        self.credits
+    }
+
+    fn credits_mut(&mut self) -> &mut f64 {
+        &mut self.credits
     }
-}
\ No newline at end of file
+
+    fn knowledge(&self) -> &Knowledge {
+        &self.knowledge
+    }
+
+    fn get_time(&self) -> Time {
+        self.time
+    }
+
+    fn step(&mut self, dt: Time) {
+        self.time.0 += dt.0;
+        self.run_power(dt);
+        self.run_ai();
+        self.run_orders(dt);
+        self.run_collisions();
+        self.run_shipyards(dt);
+        self.run_refineries(dt);
+        self.sweep_sensors();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_spawns_leave_nothing_behind() {
+        let mut galaxy = Galaxy::new();
+        let earth = galaxy.iter().find(|(_, object)| object.name == "Earth").unwrap().0;
+        let count = galaxy.iter().count();
+
+        let below = galaxy.spawn_object(ObjectBuilder::default().name("Low"), ParentBuilder::Relation(earth, Relation::Orbit(10)));
+        assert_eq!(below.unwrap_err(), OrbitError::BelowSurface);
+
+        // a child that can't be placed takes its parent down with it
+        let moonlet = galaxy.spawn_object(
+            ObjectBuilder::default()
+                .name("Moonlet")
+                .child(ObjectBuilder::default().name("Station").kind(ObjectKind::Structure(Structure::new(vec![]))), Relation::Docked),
+            ParentBuilder::Relation(earth, Relation::Orbit(20_000)),
+        );
+        assert_eq!(moonlet.unwrap_err(), OrbitError::NotAStructure);
+        assert_eq!(galaxy.iter().count(), count);
+        assert!(galaxy.iter().all(|(_, object)| object.name != "Moonlet"));
+    }
+
+    #[test]
+    fn destroyed_objects_are_forgotten() {
+        let mut galaxy = Galaxy::new();
+        galaxy.step(Time(1.));
+        let earth = galaxy.ancestry(galaxy.fleet()[0])[1];
+        assert!(galaxy.knowledge().is_known(earth));
+        galaxy.destroy(earth);
+        assert!(!galaxy.knowledge().is_known(earth));
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::Display;
+
+use crate::{
+    object::{Composition, Location, Object, ObjectHandle, Parent, Relation, Resource},
+    space::{Distance, Galaxy, Mass, World},
+};
+
+/// gravitational constant, in km³/(kg s²)
+pub(crate) const G: f64 = 6.674e-20;
+/// standard gravity, in km/s²
+const STANDARD_GRAVITY: f64 = 9.806_65e-3;
+/// exhaust velocity of thrusters, in km/s
+const EXHAUST_VELOCITY: f64 = 30.;
+/// mass of a site's deposit, as a fraction of its body's mass
+const DEPOSIT_FRACTION: f64 = 1e-12;
+/// how much richer a deposit is in its main resource than the body as a whole
+const ENRICHMENT: f32 = 3.;
+
+/// kind of region a surface site covers, each rich in one resource
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Terrain {
+    Highlands,
+    IceCap,
+    OreField,
+}
+
+/// a region of a body's surface with a deposit to mine and room for landers and structures
+#[derive(Clone, Debug)]
+pub struct Site {
+    pub terrain: Terrain,
+    pub location: Location,
+    /// what mining the site yields
+    pub deposit: Composition,
+    /// mass left in the deposit
+    pub remaining: Mass,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 3] = [Terrain::Highlands, Terrain::IceCap, Terrain::OreField];
+
+    /// resource the terrain is rich in
+    pub fn resource(&self) -> Resource {
+        match self {
+            Terrain::Highlands => Resource::Rock,
+            Terrain::IceCap => Resource::Ice,
+            Terrain::OreField => Resource::Metals,
+        }
+    }
+
+    // where the terrain lies: ice near the poles, the rest spread in longitude by `spin` degrees
+    fn location(&self, spin: f32) -> Location {
+        let (latitude, offset) = match self {
+            Terrain::Highlands => (12., 0.),
+            Terrain::IceCap => (85., 120.),
+            Terrain::OreField => (-23., 240.),
+        };
+        Location { latitude, longitude: (spin + offset) % 360. - 180. }
+    }
+}
+
+impl Display for Terrain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Terrain::Highlands => "highlands",
+            Terrain::IceCap => "ice cap",
+            Terrain::OreField => "ore field",
+        })
+    }
+}
+
+impl Site {
+    /// regions of a body's surface, one per solid resource it holds. stars and gas giants, with
+    /// nothing solid to land on, have none.
+    pub fn survey(composition: &Composition, radius: Distance, mass: Mass) -> Vec<Site> {
+        if composition.get(Resource::Hydrogen) + composition.get(Resource::Helium) > 0.5 {
+            return vec![];
+        }
+        let spin = (radius.0 * 7.) as f32 % 360.;
+        Terrain::ALL
+            .iter()
+            .filter(|terrain| composition.get(terrain.resource()) >= 0.01)
+            .map(|terrain| Site {
+                terrain: *terrain,
+                location: terrain.location(spin),
+                deposit: enrich(composition, terrain.resource()),
+                remaining: Mass(mass.0 * DEPOSIT_FRACTION * composition.get(terrain.resource()) as f64),
+            })
+            .collect()
+    }
+}
+
+// the composition with `resource` made `ENRICHMENT` times more abundant, at the expense of the rest
+fn enrich(composition: &Composition, resource: Resource) -> Composition {
+    let share = (composition.get(resource) * ENRICHMENT).min(1.);
+    let rest = 1. - composition.get(resource);
+    let scale = |r: Resource| {
+        if r == resource {
+            share
+        } else if rest > 0. {
+            composition.get(r) / rest * (1. - share)
+        } else {
+            0.
+        }
+    };
+    let [hydrogen, helium, rock, ice, metals] = Composition::ELEMENTS.map(scale);
+    Composition::new(hydrogen, helium, rock, ice, metals).unwrap_or_else(|_| composition.clone())
+}
+
+impl Object {
+    /// gravity at the surface of a body, in km/s²
+    pub fn surface_gravity(&self) -> Option<f64> {
+        let radius = self.radius()?;
+        Some(G * self.mass.0 / (radius.0 * radius.0))
+    }
+
+    /// surface gravity in multiples of Earth's
+    pub fn surface_gravity_g(&self) -> Option<f64> {
+        Some(self.surface_gravity()? / STANDARD_GRAVITY)
+    }
+
+    /// delta-v needed to land on the body from a low orbit or to take off back to it, in km/s
+    pub fn landing_delta_v(&self) -> Option<f64> {
+        Some((self.surface_gravity()? * self.radius()?.0).sqrt())
+    }
+}
+
+/// fuel a ship of `mass` burns to change its velocity by `delta_v` km/s
+pub fn fuel_for(mass: Mass, delta_v: f64) -> Mass {
+    Mass(mass.0 * (1. - (-delta_v / EXHAUST_VELOCITY).exp()))
+}
+
+impl Galaxy {
+    /// index of the body's site at `location`
+    pub fn site_at(&self, body: ObjectHandle, location: Location) -> Option<usize> {
+        self.sites(body).iter().position(|site| site.location == location)
+    }
+
+    /// site the object is landed at, with its body
+    pub fn landing_site(&self, object_handle: ObjectHandle) -> Option<(ObjectHandle, usize)> {
+        let Some(Relation::Landed { location }) = self.relation(object_handle) else { return None };
+        let Parent::Relation(body) = self.get_object(object_handle)?.parent else { return None };
+        Some((body, self.site_at(body, *location)?))
+    }
+
+    /// objects landed at a site
+    pub fn landed_at(&self, body: ObjectHandle, site: usize) -> Vec<ObjectHandle> {
+        let Some(location) = self.sites(body).get(site).map(|site| site.location) else { return vec![] };
+        self.get_object(body)
+            .and_then(|body| body.children.as_ref())
+            .map_or(vec![], |children| {
+                children
+                    .iter()
+                    .filter(|(_, relation)| matches!(relation, Relation::Landed { location: at } if *at == location))
+                    .map(|(child, _)| *child)
+                    .collect()
+            })
+    }
+}
@@ -0,0 +1,236 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::{
+    faction::Owner,
+    object::{ComponentKind, Composition, ObjectHandle, ObjectKind, Resource},
+    space::{Distance, Galaxy, Mass, World},
+};
+
+/// a column of the object table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Column {
+    #[default]
+    Name,
+    Kind,
+    Mass,
+    Radius,
+    /// share of the resource in the composition
+    Share(Resource),
+    /// from the controlled ship, along the hierarchy
+    Distance,
+    Owner,
+}
+
+impl Column {
+    pub const ALL: [Column; 11] = [
+        Column::Name,
+        Column::Kind,
+        Column::Mass,
+        Column::Radius,
+        Column::Share(Resource::Hydrogen),
+        Column::Share(Resource::Helium),
+        Column::Share(Resource::Rock),
+        Column::Share(Resource::Ice),
+        Column::Share(Resource::Metals),
+        Column::Distance,
+        Column::Owner,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Kind => "kind",
+            Column::Mass => "mass",
+            Column::Radius => "radius",
+            Column::Share(Resource::Hydrogen) => "H",
+            Column::Share(Resource::Helium) => "He",
+            Column::Share(Resource::Rock) => "rock",
+            Column::Share(Resource::Ice) => "ice",
+            Column::Share(_) => "metal",
+            Column::Distance => "distance",
+            Column::Owner => "owner",
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Column::Name => 18,
+            Column::Kind => 8,
+            Column::Mass | Column::Radius => 11,
+            Column::Share(_) => 6,
+            Column::Distance => 14,
+            Column::Owner => 18,
+        }
+    }
+
+    /// whether the values line up on the right, as numbers do
+    pub fn numeric(&self) -> bool {
+        !matches!(self, Column::Name | Column::Kind | Column::Owner)
+    }
+}
+
+/// kinds of objects the table can be limited to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Body,
+    Field,
+    Station,
+    Ship,
+}
+
+impl Kind {
+    pub fn of(kind: &ObjectKind) -> Self {
+        match kind {
+            ObjectKind::Body(_) => Kind::Body,
+            ObjectKind::Field(_) => Kind::Field,
+            ObjectKind::Structure(structure) if structure.count(ComponentKind::Thruster) > 0 => Kind::Ship,
+            ObjectKind::Structure(_) => Kind::Station,
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Kind::Body => "body",
+            Kind::Field => "field",
+            Kind::Station => "station",
+            Kind::Ship => "ship",
+        })
+    }
+}
+
+/// a line of the object table, with what the player knows of the object
+#[derive(Clone, Debug)]
+pub struct TableRow {
+    pub object: ObjectHandle,
+    pub name: &'static str,
+    pub kind: Kind,
+    /// estimated mass, as last seen
+    pub mass: Mass,
+    pub radius: Option<Distance>,
+    pub composition: Option<Composition>,
+    pub distance: Option<Distance>,
+    pub owner: Option<Owner>,
+}
+
+impl TableRow {
+    /// what the player knows of the object, `None` if it was never seen
+    pub fn new(world: &Galaxy, object_handle: ObjectHandle) -> Option<Self> {
+        let object = world.get_object(object_handle)?;
+        let contact = world.knowledge().get(object_handle)?;
+        Some(TableRow {
+            object: object_handle,
+            name: object.name,
+            kind: Kind::of(&object.kind),
+            mass: contact.mass,
+            radius: object.radius(),
+            composition: contact.composition.clone(),
+            distance: world.get_handle().and_then(|ship| world.distance(ship, object_handle)),
+            owner: object.owner,
+        })
+    }
+
+    /// the row's value in a numeric column
+    pub fn value(&self, column: Column) -> Option<f64> {
+        match column {
+            Column::Mass => Some(self.mass.0),
+            Column::Radius => self.radius.map(|radius| radius.0),
+            Column::Share(resource) => self.composition.as_ref().map(|composition| composition.get(resource) as f64),
+            Column::Distance => self.distance.map(|distance| distance.0),
+            Column::Name | Column::Kind | Column::Owner => None,
+        }
+    }
+
+    /// the row's value in the column, as shown
+    pub fn cell(&self, column: Column, world: &Galaxy) -> String {
+        let unknown = || "-".to_string();
+        match column {
+            Column::Name => self.name.to_string(),
+            Column::Kind => self.kind.to_string(),
+            Column::Mass => self.mass.to_string(),
+            Column::Radius => self.radius.map_or_else(unknown, |radius| format!("{:.0}km", radius.0)),
+            Column::Share(resource) => {
+                self.composition.as_ref().map_or_else(unknown, |composition| format!("{:.0}%", composition.get(resource) * 100.))
+            }
+            Column::Distance => self.distance.map_or_else(unknown, |distance| format!("{:.0}km", distance.0)),
+            Column::Owner => self.owner.map_or_else(unknown, |owner| world.factions().name(owner).to_string()),
+        }
+    }
+
+    // orders by the column, unknown values last
+    fn compare(&self, other: &Self, column: Column, world: &Galaxy) -> Ordering {
+        fn known<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            }
+        }
+        match column {
+            Column::Name => self.name.cmp(other.name),
+            Column::Kind => self.kind.to_string().cmp(&other.kind.to_string()),
+            Column::Mass | Column::Radius | Column::Share(_) | Column::Distance => known(self.value(column), other.value(column)),
+            Column::Owner => known(
+                self.owner.map(|owner| world.factions().name(owner)),
+                other.owner.map(|owner| world.factions().name(owner)),
+            ),
+        }
+    }
+}
+
+/// what the object table lists, and in which order
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    /// selected column, the one sorting acts on
+    pub column: usize,
+    /// column sorted by
+    pub sort: Column,
+    pub descending: bool,
+    /// only list objects of the kind
+    pub kind: Option<Kind>,
+    /// only list objects in the same system as the inspected object
+    pub system: bool,
+    /// only list objects whose name contains it, ignoring case
+    pub filter: String,
+    /// selected row
+    pub index: usize,
+}
+
+impl Table {
+    /// sorts by the selected column, or reverses the order if it already is the one
+    pub fn sort_selected(&mut self) {
+        let column = Column::ALL[self.column];
+        self.descending = self.sort == column && !self.descending;
+        self.sort = column;
+    }
+
+    pub fn cycle_kind(&mut self) {
+        self.kind = match self.kind {
+            None => Some(Kind::Body),
+            Some(Kind::Body) => Some(Kind::Field),
+            Some(Kind::Field) => Some(Kind::Station),
+            Some(Kind::Station) => Some(Kind::Ship),
+            Some(Kind::Ship) => None,
+        };
+    }
+
+    /// known objects passing the filters, sorted, with `view` telling which system is inspected
+    pub fn rows(&self, world: &Galaxy, view: Option<ObjectHandle>) -> Vec<TableRow> {
+        let system = view.and_then(|view| world.ancestry(view).first().copied());
+        let filter = self.filter.to_lowercase();
+        let mut rows: Vec<TableRow> = world
+            .iter()
+            .filter(|(handle, object)| {
+                self.kind.is_none_or(|only| only == Kind::of(&object.kind))
+                    && object.name.to_lowercase().contains(&filter)
+                    && (!self.system || world.ancestry(*handle).first().copied() == system)
+            })
+            .filter_map(|(handle, _)| TableRow::new(world, handle))
+            .collect();
+        rows.sort_by(|a, b| {
+            let order = a.compare(b, self.sort, world);
+            if self.descending { order.reverse() } else { order }
+        });
+        rows
+    }
+}
@@ -0,0 +1,283 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::{
+    object::{ObjectHandle, ObjectKind, Parent, Relation},
+    space::{Distance, Galaxy, Time, World},
+    surface::G,
+};
+
+/// a transfer between two orbits around the same body
+#[derive(Clone, Copy, Debug)]
+pub struct TransferPlan {
+    /// when to leave
+    pub departure: Time,
+    /// how long the flight takes
+    pub duration: Time,
+    /// total change of velocity to leave the first orbit and enter the second, in km/s
+    pub delta_v: f64,
+}
+
+/// delta-v of transfers over a grid of departure times and flight durations
+#[derive(Clone, Debug)]
+pub struct Porkchop {
+    pub departures: Vec<Time>,
+    pub flights: Vec<Time>,
+    /// delta-v in km/s indexed by flight then departure, `None` where no transfer fits
+    pub delta_v: Vec<Vec<Option<f64>>>,
+}
+
+impl Porkchop {
+    /// the cheapest transfer of the grid, as flight and departure indices
+    pub fn cheapest(&self) -> Option<(usize, usize)> {
+        self.delta_v
+            .iter()
+            .enumerate()
+            .flat_map(|(flight, row)| row.iter().enumerate().filter_map(move |(departure, dv)| Some((flight, departure, (*dv)?))))
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(flight, departure, _)| (flight, departure))
+    }
+}
+
+impl Galaxy {
+    /// the closest object both objects descend from, either of them included
+    pub fn common_ancestor(&self, a: ObjectHandle, b: ObjectHandle) -> Option<ObjectHandle> {
+        self.ancestry(a)
+            .into_iter()
+            .zip(self.ancestry(b))
+            .take_while(|(a, b)| a == b)
+            .last()
+            .map(|(ancestor, _)| ancestor)
+    }
+
+    /// distance of a transfer's start and end from the common ancestor of the ship and its
+    /// target, as if all the orbits in between lined up
+    pub fn transfer_radii(&self, ship: ObjectHandle, target: ObjectHandle) -> Option<(Distance, Distance)> {
+        let common = self.common_ancestor(ship, target)?;
+        let parking = self.parking_orbit(ship, target).distance();
+        Some((self.distance(ship, common)?, Distance(self.distance(target, common)?.0 + parking.0)))
+    }
+
+    /// moves a ship on its way to the target `radius` away from `common`, the ancestor they shared
+    /// when it left, reparenting it under whichever body's sphere of influence it is in.
+    /// only ships in transit cross spheres this way, anything else stays with the parent it has.
+    pub(crate) fn follow_transfer(&mut self, ship: ObjectHandle, target: ObjectHandle, common: ObjectHandle, radius: Distance) {
+        let radius = radius.0;
+
+        // bodies and fields between the common ancestor and either end, deepest first
+        let below = |ancestry: Vec<ObjectHandle>| -> Vec<ObjectHandle> {
+            ancestry.into_iter().skip_while(|handle| *handle != common).skip(1).collect()
+        };
+        let mut wells: Vec<(usize, ObjectHandle)> = [below(self.ancestry(ship)), below(self.ancestry(target))]
+            .into_iter()
+            .flat_map(|chain| chain.into_iter().enumerate())
+            .filter(|(_, handle)| *handle != ship)
+            .filter(|(_, handle)| !matches!(self.get_object(*handle).unwrap().kind, ObjectKind::Structure(_)))
+            .collect();
+        wells.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+
+        let (parent, altitude) = wells
+            .into_iter()
+            .find_map(|(_, well)| {
+                let separation = (radius - self.distance(well, common)?.0).abs();
+                (separation < self.soi(well)?.0).then_some((well, separation))
+            })
+            .unwrap_or((common, radius));
+        // never closer than a parking orbit, so the ship doesn't graze the body on its way
+        let floor = self.parking_orbit(ship, parent).distance().0;
+        let relation = Relation::Orbit(altitude.max(floor) as usize);
+        if matches!(self.get_object(ship).unwrap().parent, Parent::Relation(current) if current == parent) {
+            // still in the same sphere, only the altitude changes
+            self.get_object_mut(parent).unwrap().insert_child(ship, relation);
+        } else {
+            self.reparent(ship, parent, relation);
+        }
+    }
+
+    // the objects below the common ancestor of the ship and target that carry each of them
+    // around it, along with that ancestor. both have to be in orbit.
+    fn transfer_legs(&self, ship: ObjectHandle, target: ObjectHandle) -> Option<(ObjectHandle, ObjectHandle, ObjectHandle)> {
+        let common = self.common_ancestor(ship, target)?;
+        let leg = |object_handle| {
+            let ancestry = self.ancestry(object_handle);
+            let leg = *ancestry.get(ancestry.iter().position(|handle| *handle == common)? + 1)?;
+            matches!(self.relation(leg), Some(Relation::Orbit(_))).then_some(leg)
+        };
+        Some((common, leg(ship)?, leg(target)?))
+    }
+
+    /// time an object takes to go around its parent once
+    pub fn orbital_period(&self, object_handle: ObjectHandle) -> Option<Time> {
+        let Some(Relation::Orbit(radius)) = self.relation(object_handle) else { return None };
+        let Parent::Relation(parent) = self.get_object(object_handle)?.parent else { return None };
+        let radius = *radius as f64;
+        Some(Time(TAU * (radius.powi(3) / (G * self.get_object(parent)?.mass.0)).sqrt()))
+    }
+
+    /// angle of an object along its orbit at `time`, in radians. orbits are circular and
+    /// prograde, each starting out at an angle picked from its radius.
+    pub fn orbital_angle(&self, object_handle: ObjectHandle, time: Time) -> Option<f64> {
+        let Some(Relation::Orbit(radius)) = self.relation(object_handle) else { return None };
+        let start = (*radius as f64 * 0.618_033_988_75).fract() * TAU;
+        Some((start + TAU * time.0 / self.orbital_period(object_handle)?.0).rem_euclid(TAU))
+    }
+
+    /// time between two alignments of the ship and target around their common ancestor
+    pub fn synodic_period(&self, ship: ObjectHandle, target: ObjectHandle) -> Option<Time> {
+        let (_, from, to) = self.transfer_legs(ship, target)?;
+        let rate = 1. / self.orbital_period(from)?.0 - 1. / self.orbital_period(to)?.0;
+        (rate != 0.).then(|| Time(1. / rate.abs()))
+    }
+
+    /// the cheapest two-burn transfer from the ship's orbit to the target's, leaving at the next
+    /// window when the target is ahead by the right angle
+    pub fn hohmann(&self, ship: ObjectHandle, target: ObjectHandle) -> Option<TransferPlan> {
+        let (common, from, to) = self.transfer_legs(ship, target)?;
+        let mu = G * self.get_object(common)?.mass.0;
+        let (r1, r2) = (self.get_object(common)?.get_child(from)?.distance().0, self.get_object(common)?.get_child(to)?.distance().0);
+
+        let semi_major = (r1 + r2) / 2.;
+        let duration = PI * (semi_major.powi(3) / mu).sqrt();
+        let delta_v = ((mu / r1).sqrt() * ((2. * r2 / (r1 + r2)).sqrt() - 1.)).abs()
+            + ((mu / r2).sqrt() * (1. - (2. * r1 / (r1 + r2)).sqrt())).abs();
+
+        // the target has to be where the ship will be half an orbit later
+        let (n1, n2) = (TAU / self.orbital_period(from)?.0, TAU / self.orbital_period(to)?.0);
+        let now = self.get_time();
+        let lead = PI - n2 * duration;
+        let ahead = self.orbital_angle(to, now)? - self.orbital_angle(from, now)?;
+        let wait = if n1 == n2 { 0. } else { ((lead - ahead) * (n2 - n1).signum()).rem_euclid(TAU) / (n2 - n1).abs() };
+
+        Some(TransferPlan { departure: Time(now.0 + wait), duration: Time(duration), delta_v })
+    }
+
+    /// delta-v of transfers leaving at `departures` evenly spread over the next synodic period and
+    /// taking `flights` durations around the Hohmann one
+    pub fn porkchop(&self, ship: ObjectHandle, target: ObjectHandle, departures: usize, flights: usize) -> Option<Porkchop> {
+        let (common, from, to) = self.transfer_legs(ship, target)?;
+        let hohmann = self.hohmann(ship, target)?;
+        let mu = G * self.get_object(common)?.mass.0;
+        let span = self.synodic_period(ship, target).map_or(2. * hohmann.duration.0, |synodic| synodic.0);
+
+        let now = self.get_time().0;
+        let departures: Vec<Time> = (0..departures).map(|i| Time(now + span * i as f64 / departures as f64)).collect();
+        let flights: Vec<Time> =
+            (0..flights).map(|i| Time(hohmann.duration.0 * (0.5 + i as f64 / flights.max(2).saturating_sub(1) as f64))).collect();
+
+        // position and velocity of an orbiting object at a time
+        let state = |object_handle, time: f64| -> Option<([f64; 2], [f64; 2])> {
+            let radius = self.get_object(common)?.get_child(object_handle)?.distance().0;
+            let angle = self.orbital_angle(object_handle, Time(time))?;
+            let speed = (mu / radius).sqrt();
+            Some(([radius * angle.cos(), radius * angle.sin()], [-speed * angle.sin(), speed * angle.cos()]))
+        };
+        let delta_v = flights
+            .iter()
+            .map(|flight| {
+                departures
+                    .iter()
+                    .map(|departure| {
+                        let (r1, v1) = state(from, departure.0)?;
+                        let (r2, v2) = state(to, departure.0 + flight.0)?;
+                        let (leave, arrive) = lambert(r1, r2, flight.0, mu)?;
+                        Some((leave[0] - v1[0]).hypot(leave[1] - v1[1]) + (arrive[0] - v2[0]).hypot(arrive[1] - v2[1]))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Some(Porkchop { departures, flights, delta_v })
+    }
+}
+
+// velocities at both ends of the prograde path from `r1` to `r2` taking `duration` around a body
+// of gravitational parameter `mu`, solved with universal variables
+fn lambert(r1: [f64; 2], r2: [f64; 2], duration: f64, mu: f64) -> Option<([f64; 2], [f64; 2])> {
+    let (l1, l2) = (r1[0].hypot(r1[1]), r2[0].hypot(r2[1]));
+    let cos = ((r1[0] * r2[0] + r1[1] * r2[1]) / (l1 * l2)).clamp(-1., 1.);
+    let mut angle = cos.acos();
+    if r1[0] * r2[1] - r1[1] * r2[0] < 0. {
+        angle = TAU - angle;
+    }
+    let a = angle.sin() * (l1 * l2 / (1. - cos)).sqrt();
+
+    let y = |z: f64| l1 + l2 + a * (z * stumpff_s(z) - 1.) / stumpff_c(z).sqrt();
+    let time = |z: f64| {
+        let y = y(z);
+        if y < 0. { -1. } else { ((y / stumpff_c(z)).powf(1.5) * stumpff_s(z) + a * y.sqrt()) / mu.sqrt() }
+    };
+    // flight time grows with z, so bisect for the one matching the duration
+    let (mut low, mut high) = (-4. * TAU * TAU, TAU * TAU - 1e-9);
+    for _ in 0..100 {
+        let z = (low + high) / 2.;
+        if time(z) < duration {
+            low = z;
+        } else {
+            high = z;
+        }
+    }
+    let z = (low + high) / 2.;
+    // too slow or too fast to get there without going around
+    if (time(z) - duration).abs() > duration * 1e-3 {
+        return None;
+    }
+    let y = y(z);
+
+    let f = 1. - y / l1;
+    let g = a * (y / mu).sqrt();
+    let g_dot = 1. - y / l2;
+    let leave = [(r2[0] - f * r1[0]) / g, (r2[1] - f * r1[1]) / g];
+    let arrive = [(g_dot * r2[0] - r1[0]) / g, (g_dot * r2[1] - r1[1]) / g];
+    leave.iter().chain(&arrive).all(|v| v.is_finite()).then_some((leave, arrive))
+}
+
+fn stumpff_c(z: f64) -> f64 {
+    if z > 0. {
+        (1. - z.sqrt().cos()) / z
+    } else if z < 0. {
+        ((-z).sqrt().cosh() - 1.) / -z
+    } else {
+        0.5
+    }
+}
+
+fn stumpff_s(z: f64) -> f64 {
+    if z > 0. {
+        (z.sqrt() - z.sqrt().sin()) / z.powf(1.5)
+    } else if z < 0. {
+        ((-z).sqrt().sinh() - (-z).sqrt()) / (-z).powf(1.5)
+    } else {
+        1. / 6.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hohmann_earth_to_mars() {
+        let galaxy = Galaxy::new();
+        let ship = galaxy.fleet()[0];
+        let mars = galaxy.find_child(galaxy.ancestry(ship)[0], "Mars").unwrap();
+
+        let plan = galaxy.hohmann(ship, mars).unwrap();
+        assert!((plan.delta_v - 5.59).abs() < 0.02, "{}km/s", plan.delta_v);
+        assert!((plan.duration.0 / 86_400. - 259.).abs() < 1., "{}", plan.duration);
+        assert!(plan.departure.0 >= galaxy.get_time().0);
+        assert!(plan.departure.0 - galaxy.get_time().0 <= galaxy.synodic_period(ship, mars).unwrap().0);
+    }
+
+    #[test]
+    fn lambert_follows_circular_orbits() {
+        // a low Earth orbit, whose speed is sqrt(mu / r)
+        let (mu, r): (f64, f64) = (398_600., 7_000.);
+        let (speed, period) = ((mu / r).sqrt(), TAU * (r * r * r / mu).sqrt());
+        let close = |a: [f64; 2], b: [f64; 2]| (a[0] - b[0]).hypot(a[1] - b[1]) < 1e-3 * speed;
+
+        let (leave, arrive) = lambert([r, 0.], [0., r], period / 4., mu).unwrap();
+        assert!(close(leave, [0., speed]) && close(arrive, [-speed, 0.]), "{leave:?} {arrive:?}");
+        // the long way round, still prograde
+        let (leave, arrive) = lambert([r, 0.], [0., -r], period * 3. / 4., mu).unwrap();
+        assert!(close(leave, [0., speed]) && close(arrive, [speed, 0.]), "{leave:?} {arrive:?}");
+    }
+}
@@ -3,21 +3,57 @@ use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, BorderType, Paragraph, Widget, Wrap},
+    widgets::{Block, BorderType, Clear, Paragraph, Widget},
 };
 
-use crate::{app::App, object::Parent, space::World};
+use crate::{
+    app::{App, Input, Panel},
+    keymap::Action,
+    message::Severity,
+    faction::{Owner, Standing},
+    object::{Component, ComponentKind, Composition, Object, ObjectHandle, ObjectKind, Parent, Relation, Resource, Structure},
+    orders::{Order, OrderState},
+    shipyard::{BLUEPRINTS, JobState},
+    space::{Mass, Time, World},
+    surface::fuel_for,
+    table::Column,
+};
+
+/// what a click on part of the screen selects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hit {
+    /// a breadcrumb segment, by view index
+    Crumb(usize),
+    /// a row of the list in the main area
+    Row(usize),
+    /// a flight and departure of the transfer planner
+    Cell(usize, usize),
+}
 
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let block = Block::bordered()
+        self.hits.borrow_mut().clear();
+        let clock = if self.paused {
+            format!(" T+{} paused ", self.world.get_time())
+        } else {
+            format!(" T+{} x{} ", self.world.get_time(), self.warp)
+        };
+        let mut block = Block::bordered()
             .title("spacers")
+            .title_top(Line::from(clock).right_aligned())
             .title_alignment(Alignment::Center)
             .border_type(BorderType::Rounded);
+        if !self.keymap_problems.is_empty() {
+            let problems = format!(" keymap: {} ", self.keymap_problems.join("; "));
+            block = block.title_bottom(Line::from(problems).left_aligned().style(Style::new().fg(Color::Red)));
+        }
 
         block.render(area, buf);
 
         render_viewport(self, area, buf);
+        if self.help {
+            render_help(self, area, buf);
+        }
     }
 }
 
@@ -28,35 +64,57 @@ fn render_viewport(app: &App, area: Rect, buf: &mut Buffer) {
 
     let height = render_path(app, layout[0], buf);
 
-    let layout = Layout::vertical(vec![Constraint::Length(height), Constraint::Fill(1)])
+    let layout = Layout::vertical(vec![Constraint::Length(height), Constraint::Fill(1), Constraint::Length(1)])
         .margin(1)
         .split(area);
+    render_footer(app, layout[2], buf);
 
-    render_view(app, layout[1], buf);
+    match app.panel {
+        Panel::View => render_view(app, layout[1], buf),
+        Panel::Fleet => render_fleet(app, layout[1], buf),
+        Panel::Orders => render_orders(app, layout[1], buf),
+        Panel::Shipyard => render_shipyard(app, layout[1], buf),
+        Panel::Production => render_production(app, layout[1], buf),
+        Panel::Power => render_power(app, layout[1], buf),
+        Panel::Market => render_market(app, layout[1], buf),
+        Panel::Planner => render_planner(app, layout[1], buf),
+        Panel::Log => render_log(app, layout[1], buf),
+        Panel::Bookmarks => render_bookmarks(app, layout[1], buf),
+        Panel::Tree => render_tree(app, layout[1], buf),
+        Panel::Table => render_table(app, layout[1], buf),
+        Panel::Compare => render_compare(app, layout[1], buf),
+    }
+    render_toasts(app, layout[1], buf);
 }
 
-fn generate_path(app: &App) -> Paragraph<'_> {
-    let mut line = Line::from(Span::from("Space").style(if app.view_index == Some(0) {
-        Style::new().bg(Color::Yellow).fg(Color::Blue).bold()
-    } else if app.view.len() == 0 {
-        Style::new().fg(Color::Green)
-    } else {
-        Style::new().fg(Color::Blue)
-    }));
+// breadcrumb segments, each with the view index that selects it
+fn generate_path(app: &App) -> Vec<(Span<'_>, Option<usize>)> {
+    let mut spans = vec![(
+        Span::from("Space").style(if app.view_index == Some(0) {
+            Style::new().bg(Color::Yellow).fg(Color::Blue).bold()
+        } else if app.view.is_empty() {
+            Style::new().fg(Color::Green)
+        } else {
+            Style::new().fg(Color::Blue)
+        }),
+        Some(0),
+    )];
     app.view
         .iter()
         .enumerate()
         .for_each(|(idx, object_handle)| {
-            line.push_span(Span::from(" > ").style(Style::new().fg(Color::DarkGray)));
-            line.push_span(
-                Span::from(format!(
-                    "{}",
-                    app.world.get_object(*object_handle).unwrap().name
-                ))
+            spans.push((Span::from(" > ").style(Style::new().fg(Color::DarkGray)), None));
+            let name = if app.world.knowledge().is_known(*object_handle) {
+                app.world.get_object(*object_handle).unwrap().name
+            } else {
+                "Unknown"
+            };
+            spans.push((
+                Span::from(name)
                 .style(if app.view_index == Some(idx + 1) {
                     Style::new().bg(Color::Yellow).fg(Color::Blue).bold()
                 } else if app.view.len() == idx + 1 {
-                    if app.view_index == None {
+                    if app.view_index.is_none() {
                         Style::new().fg(Color::Green).bold()
                     } else {
                         Style::new().fg(Color::Green)
@@ -64,35 +122,62 @@ fn generate_path(app: &App) -> Paragraph<'_> {
                 } else {
                     Style::new().fg(Color::Blue)
                 }),
-            );
+                Some(idx + 1),
+            ));
         });
-    if app.view_index != None {
-        line.push_span(Span::from(" [Esc]").style(Style::new().fg(Color::Yellow)));
-    } else if app.get_view() != app.world.get_handle() {
-        line.push_span(Span::from(" [Esc]").style(Style::new().fg(Color::Gray)));
-    } else {
-        line.push_span(Span::from(" <-You").style(Style::new().fg(Color::Gray)));
-    }
-    Paragraph::new(line).wrap(Wrap { trim: true })
+    let back = key_names(app, Action::Back);
+    spans.push((
+        if app.view_index.is_some() {
+            Span::from(format!(" [{back}]")).style(Style::new().fg(Color::Yellow))
+        } else if app.get_view() == app.world.get_handle() {
+            Span::from(" <-You").style(Style::new().fg(Color::Gray))
+        } else if app.get_view().is_some_and(|view| app.world.fleet().contains(&view)) {
+            Span::from(format!(" <-Fleet [{back}]")).style(Style::new().fg(Color::Gray))
+        } else {
+            Span::from(format!(" [{back}]")).style(Style::new().fg(Color::Gray))
+        },
+        None,
+    ));
+    spans
 }
 
+// wraps the breadcrumb between segments so each one can be clicked where it's drawn
 fn render_path(app: &App, area: Rect, buf: &mut Buffer) -> u16 {
-    let para = generate_path(app);
-    let line_count = para.line_count(area.width) as u16;
-
-    let layout =
-        Layout::vertical(vec![Constraint::Length(line_count), Constraint::Fill(1)]).split(area);
-
-    para.render(layout[0], buf);
+    let mut lines = vec![Line::default()];
+    let (mut x, mut y) = (0, 0);
+    for (span, crumb) in generate_path(app) {
+        let width = span.width() as u16;
+        if x > 0 && x + width > area.width {
+            lines.push(Line::default());
+            (x, y) = (0, y + 1);
+        }
+        if let Some(crumb) = crumb {
+            app.hit(Rect::new(area.x + x, area.y + y, width, 1), Hit::Crumb(crumb));
+        }
+        lines.last_mut().unwrap().push_span(span);
+        x += width;
+    }
 
+    let line_count = lines.len() as u16;
+    Paragraph::new(lines).render(area, buf);
     line_count
 }
 
+// makes each row of a list in `area` clickable, numbering them from `first`
+fn hit_rows(app: &App, area: Rect, first: usize, count: usize) {
+    for row in 0..count.min(area.height as usize) {
+        app.hit(Rect::new(area.x, area.y + row as u16, area.width, 1), Hit::Row(first + row));
+    }
+}
+
 fn render_view(app: &App, area: Rect, buf: &mut Buffer) {
     let layout = Layout::vertical([
         Constraint::Length(1),
         Constraint::Length(1),
         Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
     ])
     .split(area);
     let view = match app.view_index {
@@ -102,28 +187,1020 @@ fn render_view(app: &App, area: Rect, buf: &mut Buffer) {
 
     if let Some(view_handle) = view {
         let object = app.world.get_object(view_handle).unwrap();
+        let knowledge = app.world.knowledge();
+        let Some(contact) = knowledge.get(view_handle) else {
+            Line::from("No sensor data").render(area, buf);
+            return;
+        };
 
-        Line::from(format!(
-            "{}: (mass:{}kg, children:{})",
+        let mut line = Line::from(format!(
+            "{}: (mass:~{} ±{:.0}%, children:{})",
             object.name,
-            object.mass,
-            object.children_count(),
-        ))
-        .render(layout[0], buf);
+            contact.mass,
+            contact.mass_error * 100.,
+            knowledge.known_children(object).count(),
+        ));
+        if let Some(owner) = object.owner {
+            let standing = app.world.factions().standing(Some(Owner::Player), Some(owner));
+            let role = app.world.factions().pilot(view_handle).map_or(String::new(), |pilot| format!(" {}", pilot.role));
+            let label = match owner {
+                Owner::Player => format!(" owned by you{role}"),
+                Owner::Faction(_) => format!(" {}{role} ({standing})", app.world.factions().name(owner)),
+            };
+            line.push_span(Span::from(label).style(standing_style(owner, standing)));
+        }
+        if app.world.is_bookmarked(view_handle, None) {
+            line.push_span(Span::from(" *bookmarked").style(Style::new().fg(Color::Yellow)));
+        }
+        line.render(layout[0], buf);
 
         Line::from(match object.parent {
             Parent::Position(pos) => format!("at [{},{}]", pos[0], pos[1]),
             Parent::Relation(parent_handle) => {
                 let parent = app.world.get_object(parent_handle).unwrap();
-                format!(
-                    "orbiting {}: {:?}",
-                    parent.name,
-                    parent.get_child(view_handle).unwrap()
-                )
+                match parent.get_child(view_handle).unwrap() {
+                    relation @ Relation::Orbit(_) => format!("orbiting {}: {relation}", parent.name),
+                    Relation::Docked => format!("docked at {}", parent.name),
+                    Relation::Landed { location } => format!("landed on {} at {location}", parent.name),
+                    Relation::Attached => format!("attached to {}", parent.name),
+                }
             }
         })
         .render(layout[1], buf);
+
+        Line::from(match (&contact.composition, object.composition()) {
+            (Some(composition), _) => match (object.surface_gravity_g(), object.landing_delta_v()) {
+                (Some(gravity), Some(delta_v)) if !app.world.sites(view_handle).is_empty() => {
+                    format!("composition: {composition} | gravity {gravity:.2}g, landing Δv {delta_v:.2}km/s")
+                }
+                _ => format!("composition: {composition}"),
+            },
+            (None, Some(_)) => "composition: unknown".to_string(),
+            (None, None) => components_summary(&object.kind),
+        })
+        .render(layout[2], buf);
+
+        if let Some(structure) = object.structure() {
+            Line::from(format!("{} | {}", cargo_summary(&object.kind), power_summary(structure))).render(layout[3], buf);
+        } else if let Some(composition) = &contact.composition {
+            CompositionBar(composition).render(layout[3], buf);
+        }
+
+        let age = knowledge.age(view_handle, app.world.get_time()).unwrap();
+        if age.0 > 0. {
+            Line::from(format!("last seen {age} ago"))
+                .style(Style::new().fg(Color::DarkGray))
+                .render(layout[4], buf);
+        }
+    } else {
+        Line::from("Known systems:").render(layout[0], buf);
+    }
+
+    if app.view_index.is_none() {
+        render_children(app, layout[5], buf);
+    }
+}
+
+/// the share of each element of a mix as a stacked bar, labelled where there is room
+struct CompositionBar<'a>(&'a Composition);
+
+impl Widget for CompositionBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width as f32;
+        let mut start = 0.;
+        for element in Composition::ELEMENTS {
+            let share = self.0.get(element);
+            // round both ends so the segments tile the bar without gaps
+            let (from, to) = ((start * width).round() as u16, ((start + share) * width).round() as u16);
+            start += share;
+            if to <= from {
+                continue;
+            }
+            let segment = Rect::new(area.x + from, area.y, to - from, 1).intersection(area);
+            let label = [format!("{element} {:.0}%", share * 100.), format!("{:.0}%", share * 100.)]
+                .into_iter()
+                .find(|label| label.chars().count() <= segment.width as usize)
+                .unwrap_or_default();
+            Line::from(label).centered().style(Style::new().bg(element_color(element)).fg(Color::Black)).render(segment, buf);
+        }
+    }
+}
+
+fn element_color(element: Resource) -> Color {
+    match element {
+        Resource::Hydrogen => Color::LightRed,
+        Resource::Helium => Color::LightMagenta,
+        Resource::Rock => Color::Gray,
+        Resource::Ice => Color::LightCyan,
+        _ => Color::Yellow,
+    }
+}
+
+fn render_children(app: &App, area: Rect, buf: &mut Buffer) {
+    let view = app.get_view().and_then(|view| app.world.get_object(view));
+    let mut lines: Vec<Line> = app
+        .view_children()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, child)| {
+            let object = app.world.get_object(child).unwrap();
+            let relation = match (view.and_then(|view| view.get_child(child)), app.world.landing_site(child)) {
+                (Some(Relation::Landed { .. }), Some((body, site))) => {
+                    format!(" landed at the {}", app.world.sites(body)[site].terrain)
+                }
+                (Some(relation), _) => format!(" {relation}"),
+                (None, _) => String::new(),
+            };
+            let mut line = Line::from(format!(" - {}{relation}", object.name));
+            if let Some(owner) = object.owner {
+                let standing = app.world.factions().standing(Some(Owner::Player), Some(owner));
+                line.push_span(
+                    Span::from(format!(" [{}]", app.world.factions().name(owner))).style(standing_style(owner, standing)),
+                );
+            }
+            if idx == app.child_index {
+                line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold())
+            } else {
+                line
+            }
+        })
+        .collect();
+    let offset = lines.len();
+    // rows start below the filter header when there is one
+    let header = app.owner_filter.is_some() as u16;
+    hit_rows(app, Rect { y: area.y + header, height: area.height.saturating_sub(header), ..area }, 0, offset + app.view_sites().len());
+    for (idx, site) in app.view_sites().iter().enumerate() {
+        let landed = app.get_view().map_or(0, |view| app.world.landed_at(view, idx).len());
+        let bookmarked = app.get_view().is_some_and(|view| app.world.is_bookmarked(view, Some(idx)));
+        let line = Line::from(format!(
+            " {} {} at {}: {}, {} left{}",
+            if bookmarked { '*' } else { '▪' },
+            site.terrain,
+            site.location,
+            site.deposit,
+            site.remaining,
+            if landed > 0 { format!(", {landed} landed") } else { String::new() },
+        ));
+        lines.push(if offset + idx == app.child_index {
+            line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold())
+        } else {
+            line.style(Style::new().fg(Color::Gray))
+        });
+    }
+    if let Some(standing) = app.owner_filter {
+        let keys = key_names(app, Action::OwnerFilter);
+        lines.insert(0, Line::from(format!("showing {standing} only [{keys}]")).style(Style::new().fg(Color::DarkGray)));
+    }
+    Paragraph::new(lines).render(area, buf);
+}
+
+fn render_fleet(app: &App, area: Rect, buf: &mut Buffer) {
+    let active = app.world.get_handle();
+    let lines: Vec<Line> = app
+        .world
+        .fleet()
+        .iter()
+        .enumerate()
+        .map(|(idx, &ship)| {
+            let object = app.world.get_object(ship).unwrap();
+            let style = if idx == app.fleet_index {
+                Style::new().bg(Color::Yellow).fg(Color::Blue).bold()
+            } else if Some(ship) == active {
+                Style::new().fg(Color::Green)
+            } else {
+                Style::new()
+            };
+            Line::from(vec![
+                Span::from(format!("{:<12}", object.name)).style(style),
+                Span::from(format!(" {}", path_string(app, ship))).style(Style::new().fg(Color::Blue)),
+                Span::from(format!(" | {}", status(app, ship))),
+                Span::from(format!(" | {}", cargo_summary(&object.kind))).style(Style::new().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+
+    hit_rows(app, area, 0, lines.len());
+    Paragraph::new(lines).render(area, buf);
+}
+
+// names of the object's parents, as in the breadcrumb
+fn path_string(app: &App, object_handle: ObjectHandle) -> String {
+    app.world
+        .ancestry(object_handle)
+        .iter()
+        .map(|handle| if app.world.knowledge().is_known(*handle) { app.world.get_object(*handle).unwrap().name } else { "Unknown" })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+fn status(app: &App, object_handle: ObjectHandle) -> String {
+    let location = match app.world.get_object(object_handle).unwrap().parent {
+        Parent::Position(pos) => format!("drifting at [{},{}]", pos[0], pos[1]),
+        Parent::Relation(parent_handle) => {
+            let parent = app.world.get_object(parent_handle).unwrap();
+            match parent.get_child(object_handle).unwrap() {
+                Relation::Orbit(_) => format!("orbiting {}", parent.name),
+                Relation::Docked => format!("docked at {}", parent.name),
+                Relation::Landed { .. } => format!("landed on {}", parent.name),
+                Relation::Attached => format!("attached to {}", parent.name),
+            }
+        }
+    };
+    let Some(queue) = app.world.get_orders(object_handle) else {
+        return location;
+    };
+    match (&queue.state, queue.current_order()) {
+        (OrderState::Failed(error), _) => format!("{location}, failed: {error}"),
+        (state, Some(order)) => match state.progress() {
+            Some(progress) => format!("{location}, {} {:.0}%", order_label(app, order), progress * 100.),
+            None => format!("{location}, {}", order_label(app, order)),
+        },
+        (_, None) => location,
+    }
+}
+
+fn order_label(app: &App, order: &Order) -> String {
+    let name = |object_handle| app.world.get_object(object_handle).map_or("???", |object| object.name);
+    match order {
+        Order::Transfer(target) => format!("transfer to {}", name(*target)),
+        Order::Window(target, plan) => {
+            format!("transfer to {} leaving in {}", name(*target), Time(plan.departure.0 - app.world.get_time().0))
+        }
+        Order::Mine => "mine until cargo full".to_string(),
+        Order::Unload(target) => format!("unload at {}", name(*target)),
+        Order::Land(body, site) => match app.world.sites(*body).get(*site) {
+            Some(site) => format!("land on {} at the {}", name(*body), site.terrain),
+            None => format!("land on {}", name(*body)),
+        },
+        Order::Repeat => "repeat".to_string(),
+    }
+}
+
+fn render_orders(app: &App, area: Rect, buf: &mut Buffer) {
+    let Some(ship) = app.world.get_handle() else {
+        Line::from("No ship to command").render(area, buf);
+        return;
+    };
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1), Constraint::Length(1)]).split(area);
+
+    Line::from(format!("Orders for {}", app.world.get_object(ship).unwrap().name))
+        .bold()
+        .render(layout[0], buf);
+
+    let queue = app.world.get_orders(ship);
+    let lines: Vec<Line> = queue
+        .iter()
+        .flat_map(|queue| queue.orders.iter().enumerate().map(move |(idx, order)| (queue, idx, order)))
+        .map(|(queue, idx, order)| {
+            let mut line = Line::from(format!("{:>2}. {}", idx + 1, order_label(app, order)));
+            if idx == app.order_index {
+                line = line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold());
+            } else if idx < queue.current {
+                line = line.style(Style::new().fg(Color::DarkGray));
+            }
+            if idx == queue.current {
+                line.push_span(Span::from(match &queue.state {
+                    OrderState::Failed(_) => " <- failed".to_string(),
+                    state => match state.progress() {
+                        Some(progress) => format!(" <- {:.0}%", progress * 100.),
+                        None => " <-".to_string(),
+                    },
+                }));
+            }
+            line
+        })
+        .collect();
+    hit_rows(app, layout[1], 0, lines.len());
+    if lines.is_empty() {
+        Line::from("no orders").style(Style::new().fg(Color::DarkGray)).render(layout[1], buf);
+    } else {
+        Paragraph::new(lines).render(layout[1], buf);
+    }
+
+    match queue.map(|queue| (&queue.state, queue.is_done())) {
+        Some((OrderState::Failed(error), _)) => Line::from(format!("failed: {error}")).style(Style::new().fg(Color::Red)),
+        Some((_, false)) => Line::from("executing").style(Style::new().fg(Color::Green)),
+        _ => Line::from("idle").style(Style::new().fg(Color::Gray)),
+    }
+    .render(layout[2], buf);
+}
+
+fn cargo_summary(kind: &ObjectKind) -> String {
+    let ObjectKind::Structure(structure) = kind else {
+        return String::new();
+    };
+    let capacity = structure.capacity();
+    let stored = Mass(capacity.0 - structure.free().0);
+    if capacity.0 == 0. {
+        return "no cargo hold".to_string();
+    }
+    let contents: Vec<String> = Resource::ALL
+        .iter()
+        .map(|resource| (resource, structure.stored(*resource)))
+        .filter(|(_, mass)| mass.0 > 0.)
+        .map(|(resource, mass)| format!("{resource} {mass}"))
+        .collect();
+    if contents.is_empty() {
+        format!("cargo {stored}/{capacity} empty")
+    } else {
+        format!("cargo {stored}/{capacity} ({})", contents.join(", "))
+    }
+}
+
+fn components_summary(kind: &ObjectKind) -> String {
+    let ObjectKind::Structure(structure) = kind else {
+        return String::new();
+    };
+    let components: Vec<String> = ComponentKind::ALL
+        .iter()
+        .map(|kind| (kind, structure.count(*kind)))
+        .filter(|(_, count)| *count > 0)
+        .map(|(kind, count)| format!("{kind} x{count}"))
+        .collect();
+    format!("components: {}", components.join(", "))
+}
+
+fn render_shipyard(app: &App, area: Rect, buf: &mut Buffer) {
+    let station = app.get_view().and_then(|station| app.world.get_object(station));
+    let Some((station, structure)) = station
+        .and_then(|station| Some((station, station.structure()?)))
+        .filter(|(_, structure)| structure.count(ComponentKind::Shipyard) > 0)
+    else {
+        Line::from("No shipyard here").render(area, buf);
+        return;
+    };
+
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Fill(1), Constraint::Length(1)])
+        .split(area);
+    let mut title = Line::from(format!("Shipyard of {}", station.name)).bold();
+    if !app.own_station() {
+        title.push_span(Span::from(", not yours to build at").style(Style::new().fg(Color::DarkGray)));
+    }
+    title.render(layout[0], buf);
+    Line::from(cargo_summary(&station.kind)).style(Style::new().fg(Color::Gray)).render(layout[1], buf);
+
+    let columns = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).split(layout[2]);
+
+    let blueprints: Vec<Line> = BLUEPRINTS
+        .iter()
+        .enumerate()
+        .map(|(idx, blueprint)| {
+            let cost: Vec<String> = blueprint.cost.iter().map(|(resource, mass)| format!("{resource} {}", Mass(*mass))).collect();
+            let style = if idx == app.blueprint_index {
+                Style::new().bg(Color::Yellow).fg(Color::Blue).bold()
+            } else if blueprint.missing(structure).is_empty() {
+                Style::new()
+            } else {
+                Style::new().fg(Color::DarkGray)
+            };
+            Line::from(format!("{:<14} {} ({})", blueprint.name, blueprint.duration, cost.join(", "))).style(style)
+        })
+        .collect();
+    hit_rows(app, columns[0], 0, blueprints.len());
+    Paragraph::new(blueprints).render(columns[0], buf);
+
+    let jobs: Vec<Line> = structure
+        .shipyards()
+        .flat_map(|shipyard| shipyard.queue.iter())
+        .map(|job| {
+            let target = job.target.and_then(|target| app.world.get_object(target)).map_or(String::new(), |target| format!(" for {}", target.name));
+            match job.state {
+                JobState::Building { .. } => Line::from(format!("{}{target} {:.0}%", job.blueprint().name, job.progress() * 100.)),
+                JobState::Waiting => {
+                    let missing: Vec<String> = job
+                        .blueprint()
+                        .missing(structure)
+                        .iter()
+                        .map(|(resource, mass)| format!("{resource} {mass}"))
+                        .collect();
+                    if missing.is_empty() {
+                        Line::from(format!("{}{target} queued", job.blueprint().name))
+                    } else {
+                        Line::from(format!("{}{target} needs {}", job.blueprint().name, missing.join(", ")))
+                            .style(Style::new().fg(Color::Red))
+                    }
+                }
+            }
+        })
+        .collect();
+    if jobs.is_empty() {
+        Line::from("nothing being built").style(Style::new().fg(Color::DarkGray)).render(columns[1], buf);
+    } else {
+        Paragraph::new(jobs).render(columns[1], buf);
+    }
+
+    Line::from("components are fitted to your ship when in orbit")
+        .style(Style::new().fg(Color::DarkGray))
+        .render(layout[3], buf);
+}
+
+fn render_production(app: &App, area: Rect, buf: &mut Buffer) {
+    let plant = app.get_view().and_then(|plant| app.world.get_object(plant));
+    let Some((plant, structure)) = plant
+        .and_then(|plant| Some((plant, plant.structure()?)))
+        .filter(|(_, structure)| structure.count(ComponentKind::Refinery) > 0)
+    else {
+        Line::from("No refineries here").render(area, buf);
+        return;
+    };
+
+    let layout = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .split(area);
+    Line::from(format!("Production of {}", plant.name)).bold().render(layout[0], buf);
+    Line::from(cargo_summary(&plant.kind)).style(Style::new().fg(Color::Gray)).render(layout[1], buf);
+
+    // net flow of every resource over a day, at the measured utilization
+    let mut flow: Vec<(Resource, f64)> = vec![];
+    let mut add = |resource: Resource, mass: f64| match flow.iter_mut().find(|(r, _)| *r == resource) {
+        Some((_, total)) => *total += mass,
+        None => flow.push((resource, mass)),
+    };
+    for refinery in structure.refineries() {
+        let Some(recipe) = refinery.recipe() else { continue };
+        let batches = 86_400. / recipe.duration.0 * refinery.utilization();
+        for (resource, mass) in recipe.inputs {
+            add(*resource, -mass * batches);
+        }
+        for (resource, mass) in recipe.outputs {
+            add(*resource, mass * batches);
+        }
+    }
+    let flow: Vec<String> = flow
+        .iter()
+        .map(|(resource, mass)| format!("{resource} {}{}", if *mass >= 0. { "+" } else { "-" }, Mass(mass.abs())))
+        .collect();
+    Line::from(format!("{} | per day: {}", power_summary(structure), flow.join(", "))).render(layout[2], buf);
+
+    let lines: Vec<Line> = structure
+        .refineries()
+        .enumerate()
+        .map(|(idx, refinery)| {
+            let name = refinery.recipe().map_or("idle", |recipe| recipe.name);
+            let state = match (&refinery.bottleneck, refinery.batch, refinery.recipe()) {
+                (Some(bottleneck), _, _) => format!("stalled: {bottleneck}"),
+                (None, Some(elapsed), Some(recipe)) => format!("batch {:.0}%", elapsed.0 / recipe.duration.0 * 100.),
+                _ => "running".to_string(),
+            };
+            let throughput: Vec<String> = refinery
+                .throughput()
+                .iter()
+                .map(|(resource, rate)| format!("{resource} {}/day", Mass(rate * 86_400.)))
+                .collect();
+            let line = Line::from(format!(
+                "{:>2}. {name:<22} {state:<26} util {:>3.0}% {}",
+                idx + 1,
+                refinery.utilization() * 100.,
+                throughput.join(", ")
+            ));
+            if idx == app.refinery_index {
+                line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold())
+            } else if refinery.bottleneck.is_some() {
+                line.style(Style::new().fg(Color::Red))
+            } else {
+                line
+            }
+        })
+        .collect();
+    hit_rows(app, layout[3], 0, lines.len());
+    Paragraph::new(lines).render(layout[3], buf);
+}
+
+fn power_summary(structure: &Structure) -> String {
+    let power = &structure.power;
+    if power.is_brownout() {
+        format!(
+            "power {:.0}/{:.0}kW, brownout: {:.0}kW demanded, {} shed",
+            power.supplied,
+            power.generation,
+            power.demand,
+            power.shed.len()
+        )
     } else {
-        Line::from("Global info here!").render(area, buf);
+        format!("power {:.0}/{:.0}kW", power.supplied, power.generation)
+    }
+}
+
+fn render_power(app: &App, area: Rect, buf: &mut Buffer) {
+    let Some((object, structure)) = app
+        .get_view()
+        .and_then(|view| app.world.get_object(view))
+        .and_then(|object| Some((object, object.structure()?)))
+    else {
+        Line::from("No power grid here").render(area, buf);
+        return;
+    };
+
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    Line::from(format!("Power grid of {}", object.name)).bold().render(layout[0], buf);
+
+    let (output, fuel_rate) = structure
+        .reactors()
+        .fold((0., 0.), |(output, rate), reactor| (output + reactor.output, rate + reactor.fuel_rate));
+    let fuel = structure.stored(Resource::Fuel);
+    let burn = if output > 0. { structure.power.supplied / output * fuel_rate } else { 0. };
+    let endurance = if burn > 0. { format!("{} left", Time(fuel.0 / burn)) } else { "idle".to_string() };
+    Line::from(format!("{} | fuel {fuel}, {endurance}", power_summary(structure)))
+        .style(if structure.power.is_brownout() { Style::new().fg(Color::Red) } else { Style::new() })
+        .render(layout[1], buf);
+
+    let mut lines: Vec<(u8, Line)> = structure
+        .components()
+        .iter()
+        .enumerate()
+        .filter_map(|(i, component)| {
+            let kind = component.kind();
+            let line = if let Component::Reactor(reactor) = component {
+                Line::from(format!("{kind:<10} +{:>6.0}kW", reactor.output))
+                    .style(Style::new().fg(if fuel.0 > 0. { Color::Green } else { Color::Red }))
+            } else {
+                let demand = component.power_demand();
+                if demand <= 0. {
+                    return None;
+                }
+                let shed = structure.power.shed.contains(&i);
+                Line::from(format!(
+                    "{kind:<10} -{demand:>6.0}kW priority {} {}",
+                    kind.power_priority(),
+                    if shed { "shed" } else { "powered" }
+                ))
+                .style(if shed { Style::new().fg(Color::Red) } else { Style::new() })
+            };
+            Some((kind.power_priority(), line))
+        })
+        .collect();
+    lines.sort_by_key(|(priority, _)| *priority);
+    Paragraph::new(lines.into_iter().map(|(_, line)| line).collect::<Vec<_>>()).render(layout[2], buf);
+}
+
+fn render_market(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .split(area);
+
+    let ship = app.world.get_handle();
+    let station = ship.map(|ship| app.world.trading_station(ship));
+    Line::from(format!("Market | credits: {:.0}", app.world.credits())).bold().render(layout[0], buf);
+    match (station, &app.trade) {
+        (_, Some(Ok(credits))) => Line::from(format!("traded for {credits:.0} credits")).style(Style::new().fg(Color::Green)),
+        (_, Some(Err(error))) => Line::from(format!("trade failed: {error}")).style(Style::new().fg(Color::Red)),
+        (Some(Ok(station)), None) => Line::from(format!("trading at {}", app.world.get_object(station).unwrap().name)),
+        _ => Line::from("dock at a station with a market to trade").style(Style::new().fg(Color::DarkGray)),
+    }
+    .render(layout[1], buf);
+
+    // every known station with a market, as last seen
+    let knowledge = app.world.knowledge();
+    let mut stations: Vec<(ObjectHandle, &crate::knowledge::Contact)> = app
+        .world
+        .iter()
+        .filter_map(|(handle, _)| Some((handle, knowledge.get(handle)?)))
+        .filter(|(_, contact)| contact.has_market())
+        .collect();
+    stations.sort_by_key(|(handle, _)| app.world.get_object(*handle).unwrap().name);
+
+    let mut header = format!("{:<10}{:>8}", "", "ship");
+    for (handle, contact) in &stations {
+        let name = app.world.get_object(*handle).unwrap().name;
+        let age = Time(app.world.get_time().0 - contact.seen.0);
+        let name = if age.0 > 0. { format!("{name} ({age} ago)") } else { name.to_string() };
+        header.push_str(&format!(" {name:>24}"));
+    }
+    Line::from(header).style(Style::new().fg(Color::Gray)).render(layout[2], buf);
+
+    let ship_structure = ship.and_then(|ship| app.world.get_object(ship)).and_then(|ship| ship.structure());
+    let lines: Vec<Line> = Resource::ALL
+        .iter()
+        .enumerate()
+        .map(|(idx, resource)| {
+            let prices: Vec<(f64, f64)> = stations.iter().filter_map(|(_, contact)| contact.price(*resource)).collect();
+            let cheapest = prices.iter().map(|(ask, _)| *ask).fold(f64::INFINITY, f64::min);
+            let dearest = prices.iter().map(|(_, bid)| *bid).fold(0., f64::max);
+
+            let held = ship_structure.map_or(Mass(0.), |structure| structure.stored(*resource));
+            let mut line = Line::from(Span::from(format!("{resource:<10}{:>8}", held.to_string())).style(
+                if idx == app.resource_index { Style::new().bg(Color::Yellow).fg(Color::Blue).bold() } else { Style::new() },
+            ));
+            for (ask, bid) in prices {
+                let style = |best: bool| if best && stations.len() > 1 { Style::new().fg(Color::Green) } else { Style::new() };
+                line.push_span(Span::from(format!(" {:>11.2}", ask)).style(style(ask == cheapest)));
+                line.push_span(Span::from("/").style(Style::new().fg(Color::DarkGray)));
+                line.push_span(Span::from(format!("{:<12.2}", bid)).style(style(bid == dearest)));
+            }
+            line
+        })
+        .collect();
+    hit_rows(app, layout[3], 0, lines.len());
+    Paragraph::new(lines).render(layout[3], buf);
+
+    Line::from("prices are ask/bid in credits per kg")
+        .style(Style::new().fg(Color::DarkGray))
+        .render(layout[4], buf);
+}
+
+fn render_planner(app: &App, area: Rect, buf: &mut Buffer) {
+    let (Some(ship), Some(target)) = (app.world.get_handle(), app.get_view()) else {
+        Line::from("Inspect a destination to plan a transfer").render(area, buf);
+        return;
+    };
+    let (Some(hohmann), Some(porkchop)) = (app.world.hohmann(ship, target), app.porkchop()) else {
+        Line::from("No transfer: the ship and destination must both orbit the same body or bodies under it")
+            .render(area, buf);
+        return;
+    };
+
+    let layout = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(porkchop.flights.len() as u16),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .split(area);
+
+    let name = |handle| app.world.get_object(handle).unwrap().name;
+    let now = app.world.get_time().0;
+    Line::from(format!("Transfer planner | {} to {}", name(ship), name(target))).bold().render(layout[0], buf);
+    let synodic = app.world.synodic_period(ship, target).map_or(String::new(), |synodic| format!(", every {synodic}"));
+    Line::from(format!(
+        "Hohmann: {:.2}km/s, {} flight, next window in {}{synodic}",
+        hohmann.delta_v,
+        hohmann.duration,
+        Time(hohmann.departure.0 - now)
+    ))
+    .render(layout[1], buf);
+    let span = porkchop.departures.last().map_or(Time(0.), |last| Time(last.0 - now));
+    Line::from(format!("{:>10} departure from now to {span}", "flight")).style(Style::new().fg(Color::Gray)).render(layout[2], buf);
+
+    // shade each cell by how much it costs next to the cheapest
+    let cheapest = porkchop.delta_v.iter().flatten().flatten().copied().fold(f64::INFINITY, f64::min);
+    let lines: Vec<Line> = porkchop
+        .delta_v
+        .iter()
+        .enumerate()
+        .map(|(flight, row)| {
+            let mut line = Line::from(format!("{:>10} ", porkchop.flights[flight].to_string()));
+            for (departure, delta_v) in row.iter().enumerate() {
+                let color = match delta_v.map(|delta_v| delta_v / cheapest) {
+                    None => Color::DarkGray,
+                    Some(ratio) if ratio < 1.1 => Color::Green,
+                    Some(ratio) if ratio < 1.5 => Color::Yellow,
+                    Some(ratio) if ratio < 2.5 => Color::LightRed,
+                    Some(_) => Color::Red,
+                };
+                let cell = if delta_v.is_some() { "██" } else { "··" };
+                let x = layout[3].x + line.width() as u16;
+                app.hit(Rect::new(x, layout[3].y + flight as u16, 2, 1), Hit::Cell(flight, departure));
+                let style = if (flight, departure) == app.planner_cell { Style::new().fg(color).reversed() } else { Style::new().fg(color) };
+                line.push_span(Span::from(cell).style(style));
+                line.push_span(" ");
+            }
+            line
+        })
+        .collect();
+    Paragraph::new(lines).render(layout[3], buf);
+
+    let (flight, departure) = app.planner_cell;
+    let (leave, duration) = (porkchop.departures[departure], porkchop.flights[flight]);
+    let selected = match porkchop.delta_v[flight][departure] {
+        Some(delta_v) => format!(
+            "leave in {}, arrive in {} after {duration}: {delta_v:.2}km/s, burning {}",
+            Time(leave.0 - now),
+            Time(leave.0 + duration.0 - now),
+            fuel_for(app.world.get_object(ship).unwrap().loaded_mass(), delta_v)
+        ),
+        None => format!("leave in {}, {duration} flight: no direct transfer", Time(leave.0 - now)),
+    };
+    Line::from(selected).render(layout[4], buf);
+
+    Line::from("green is within 10% of the cheapest")
+        .style(Style::new().fg(Color::DarkGray))
+        .render(layout[6], buf);
+}
+
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Info => Style::new().fg(Color::Gray),
+        Severity::Warning => Style::new().fg(Color::Yellow),
+        Severity::Alert => Style::new().fg(Color::Red),
+    }
+}
+
+fn render_log(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    let shown = match app.log_filter {
+        Severity::Info => "everything".to_string(),
+        severity => format!("{severity} and above"),
+    };
+    let pausing = if app.auto_pause { "pausing on alerts" } else { "not pausing" };
+    Line::from(format!("Messages | showing {shown}, {pausing}")).bold().render(layout[0], buf);
+
+    let log = app.log();
+    if log.is_empty() {
+        Line::from("no messages").style(Style::new().fg(Color::DarkGray)).render(layout[1], buf);
+        return;
+    }
+    // scroll so the selected message stays in sight
+    let first = app.log_index.saturating_sub((layout[1].height as usize).saturating_sub(1));
+    let lines: Vec<Line> = log
+        .iter()
+        .enumerate()
+        .skip(first)
+        .map(|(idx, message)| {
+            let severity = message.notification.severity();
+            let line = Line::from(vec![
+                Span::from(format!("T+{:<10} ", message.time.to_string())).style(Style::new().fg(Color::DarkGray)),
+                Span::from(format!("{severity:<8}")).style(severity_style(severity)),
+                Span::from(message.notification.to_string()),
+            ]);
+            if idx == app.log_index { line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold()) } else { line }
+        })
+        .collect();
+    hit_rows(app, layout[1], first, lines.len());
+    Paragraph::new(lines).render(layout[1], buf);
+}
+
+// symbol of what kind of object it is, bodies by how deep they orbit
+fn kind_icon(object: &Object, depth: usize) -> char {
+    match &object.kind {
+        ObjectKind::Body(_) => ['☉', '●', '○'][depth.min(2)],
+        ObjectKind::Field(_) => '∴',
+        ObjectKind::Structure(structure) if structure.count(ComponentKind::Thruster) > 0 => '▲',
+        ObjectKind::Structure(_) => '■',
+    }
+}
+
+fn render_tree(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    Line::from("Known objects").bold().render(layout[0], buf);
+
+    let rows = app.tree_rows();
+    let selected = app.tree_index(&rows);
+    let first = selected.saturating_sub((layout[1].height as usize).saturating_sub(1));
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(layout[1].height as usize)
+        .map(|(idx, row)| {
+            let object = app.world.get_object(row.object).unwrap();
+            let fold = match (row.children, row.expanded) {
+                (0, _) => ' ',
+                (_, true) => '▾',
+                (_, false) => '▸',
+            };
+            let mut line = Line::from(format!("{}{fold} {} {}", "  ".repeat(row.depth), kind_icon(object, row.depth), object.name));
+            if row.children > 0 && !row.expanded {
+                line.push_span(Span::from(format!(" ({})", row.children)).style(Style::new().fg(Color::DarkGray)));
+            }
+            if let Some(owner) = object.owner {
+                let standing = app.world.factions().standing(Some(Owner::Player), Some(owner));
+                line.push_span(
+                    Span::from(format!(" [{}]", app.world.factions().name(owner))).style(standing_style(owner, standing)),
+                );
+            }
+            if idx == selected {
+                line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold())
+            } else if Some(row.object) == app.get_view() {
+                line.style(Style::new().fg(Color::Green))
+            } else {
+                line
+            }
+        })
+        .collect();
+    hit_rows(app, layout[1], first, lines.len());
+    Paragraph::new(lines).render(layout[1], buf);
+}
+
+// width of the property names of the comparison panel
+const PROPERTY_WIDTH: usize = 16;
+
+fn render_compare(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    Line::from(format!("Comparison | {} marked", app.compared.len())).bold().render(layout[0], buf);
+    if app.compared.is_empty() {
+        Line::from(format!("no objects marked, press {} on objects to compare them", key_names(app, Action::Mark)))
+            .style(Style::new().fg(Color::DarkGray))
+            .render(layout[2], buf);
+        return;
+    }
+
+    // the objects share what is left beside the property names
+    let width = ((area.width as usize).saturating_sub(PROPERTY_WIDTH) / app.compared.len()).clamp(8, 24) - 1;
+    let mut header = Line::from(" ".repeat(PROPERTY_WIDTH));
+    for (idx, object) in app.compared.iter().enumerate() {
+        let name: String = app.world.get_object(*object).map_or("", |object| object.name).chars().take(width).collect();
+        let style = if idx == app.compare_index { Style::new().fg(Color::Yellow).underlined() } else { Style::new().fg(Color::Gray) };
+        header.push_span(Span::from(format!("{name:>width$}")).style(style));
+        header.push_span(" ");
+    }
+    header.render(layout[1], buf);
+
+    // values are picked out where the objects differ, and the best of them stands out
+    let lines: Vec<Line> = app
+        .world
+        .compare(&app.compared)
+        .into_iter()
+        .map(|property| {
+            let mut line = Line::from(format!("{:<PROPERTY_WIDTH$}", property.name));
+            let best = property.best();
+            for (idx, cell) in property.cells.iter().enumerate() {
+                let cell: String = cell.chars().take(width).collect();
+                let style = if best == Some(idx) {
+                    Style::new().fg(Color::Green).bold()
+                } else if property.differs() {
+                    Style::new().fg(Color::Yellow)
+                } else {
+                    Style::new().fg(Color::DarkGray)
+                };
+                line.push_span(Span::from(format!("{cell:>width$}")).style(style));
+                line.push_span(" ");
+            }
+            line
+        })
+        .collect();
+    Paragraph::new(lines).render(layout[2], buf);
+}
+
+// fits the text to the column, numbers on the right
+fn pad_cell(column: Column, text: &str) -> String {
+    let width = column.width();
+    let text: String = text.chars().take(width).collect();
+    if column.numeric() { format!("{text:>width$} ") } else { format!("{text:<width$} ") }
+}
+
+fn render_table(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    let table = &app.table;
+    let kind = table.kind.map_or("every kind".to_string(), |kind| format!("{kind}s"));
+    let scope = if table.system { "in the inspected system" } else { "in the galaxy" };
+    let mut status = Line::from(format!("Objects | {kind} {scope}")).bold();
+    match (&app.input, table.filter.is_empty()) {
+        (Some(Input::TableFilter), _) => status.push_span(format!(", named like {}_", table.filter)),
+        (_, false) => status.push_span(format!(", named like {}", table.filter)),
+        (_, true) => {}
+    }
+    status.render(layout[0], buf);
+
+    // titles, with the selected column picked out and an arrow on the sorted one
+    let mut header = Line::default();
+    for (idx, column) in Column::ALL.iter().enumerate() {
+        let arrow = match (table.sort == *column, table.descending) {
+            (false, _) => "",
+            (true, false) => "▲",
+            (true, true) => "▼",
+        };
+        let title = format!("{}{arrow}", column.title());
+        let text = pad_cell(*column, &title);
+        let style = if idx == table.column { Style::new().fg(Color::Yellow).underlined() } else { Style::new().fg(Color::Gray) };
+        header.push_span(Span::from(text).style(style));
+    }
+    header.render(layout[1], buf);
+
+    let rows = table.rows(&app.world, app.get_view());
+    if rows.is_empty() {
+        Line::from("no known objects match").style(Style::new().fg(Color::DarkGray)).render(layout[2], buf);
+        return;
+    }
+    let first = table.index.saturating_sub((layout[2].height as usize).saturating_sub(1));
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(layout[2].height as usize)
+        .map(|(idx, row)| {
+            let mut line = Line::default();
+            for column in Column::ALL {
+                let text = pad_cell(column, &row.cell(column, &app.world));
+                line.push_span(match (column, row.owner) {
+                    (Column::Owner, Some(owner)) => {
+                        let standing = app.world.factions().standing(Some(Owner::Player), Some(owner));
+                        Span::from(text).style(standing_style(owner, standing))
+                    }
+                    _ => Span::from(text),
+                });
+            }
+            if idx == table.index { line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold()) } else { line }
+        })
+        .collect();
+    hit_rows(app, layout[2], first, lines.len());
+    Paragraph::new(lines).render(layout[2], buf);
+}
+
+fn render_bookmarks(app: &App, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).split(area);
+    Line::from("Bookmarks").bold().render(layout[0], buf);
+
+    let bookmarks = app.world.bookmarks();
+    if bookmarks.is_empty() {
+        Line::from(format!("no bookmarks, press {} on an object or site to add one", key_names(app, Action::Bookmark)))
+            .style(Style::new().fg(Color::DarkGray))
+            .render(layout[1], buf);
+        return;
+    }
+    let first = app.bookmark_index.saturating_sub((layout[1].height as usize).saturating_sub(1));
+    let lines: Vec<Line> = bookmarks
+        .iter()
+        .enumerate()
+        .skip(first)
+        .map(|(idx, bookmark)| {
+            let name = match &app.input {
+                Some(Input::BookmarkName(name)) if idx == app.bookmark_index => format!("{name}_"),
+                _ => bookmark.name.clone(),
+            };
+            // where it is, from the system down to its parent
+            let path = path_string(app, bookmark.object);
+            let site = match bookmark.site.and_then(|site| app.world.sites(bookmark.object).get(site)) {
+                Some(site) => format!(", {} at {}", site.terrain, site.location),
+                None => String::new(),
+            };
+            let line = Line::from(vec![
+                Span::from(format!("{name:<24} ")),
+                Span::from(format!("{path}{site}")).style(Style::new().fg(Color::DarkGray)),
+            ]);
+            if idx == app.bookmark_index { line.style(Style::new().bg(Color::Yellow).fg(Color::Blue).bold()) } else { line }
+        })
+        .collect();
+    hit_rows(app, layout[1], first, lines.len());
+    Paragraph::new(lines).render(layout[1], buf);
+}
+
+// latest notifications over the top right of the panel
+fn render_toasts(app: &App, area: Rect, buf: &mut Buffer) {
+    for (row, (message, _)) in app.toasts.iter().rev().take(3).enumerate() {
+        let text = format!(" {} ", message.notification);
+        let width = (text.chars().count() as u16).min(area.width);
+        let rect = Rect::new(area.right() - width, area.y + row as u16, width, 1).intersection(area);
+        Clear.render(rect, buf);
+        Line::from(text).style(severity_style(message.notification.severity()).reversed()).render(rect, buf);
+    }
+}
+
+// keys bound to the action, as shown in hints
+fn key_names(app: &App, action: Action) -> String {
+    let keys: Vec<String> = app.keymap.keys(action).iter().map(|key| key.to_string()).collect();
+    keys.join("/")
+}
+
+// keys for what can be done with the current focus, pairing up opposite moves
+fn render_footer(app: &App, area: Rect, buf: &mut Buffer) {
+    let hints = app.hints();
+    let mut line = Line::default();
+    let hinted = |action: Action| hints.iter().any(|(other, _)| *other == action);
+    for (action, hint) in &hints {
+        let keys = match action {
+            Action::Down if hinted(Action::Up) => continue,
+            Action::Right if hinted(Action::Left) => continue,
+            Action::Up if hinted(Action::Down) => format!("{}/{}", key_names(app, Action::Up), key_names(app, Action::Down)),
+            Action::Left if hinted(Action::Right) => format!("{}/{}", key_names(app, Action::Left), key_names(app, Action::Right)),
+            _ => key_names(app, *action),
+        };
+        line.push_span(Span::from(format!("[{keys}]")).style(Style::new().fg(Color::Gray)));
+        line.push_span(Span::from(format!(" {hint}  ")).style(Style::new().fg(Color::DarkGray)));
+    }
+    line.render(area, buf);
+}
+
+// every action available in the current mode with its keys
+fn render_help(app: &App, area: Rect, buf: &mut Buffer) {
+    let lines: Vec<Line> = app
+        .actions()
+        .into_iter()
+        .map(|action| {
+            Line::from(vec![
+                Span::from(format!("{:>16}", key_names(app, action))).style(Style::new().fg(Color::Yellow)),
+                Span::from(format!("  {}", action.description())),
+            ])
+        })
+        .collect();
+    let mode = if app.view_index.is_some() { "breadcrumb".to_string() } else { format!("{:?}", app.panel).to_lowercase() };
+
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let width = 64.min(area.width);
+    let popup = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+    Clear.render(popup, buf);
+    Paragraph::new(lines)
+        .block(Block::bordered().title(format!("keys: {mode}")).border_type(BorderType::Rounded))
+        .render(popup, buf);
+}
+
+// colours an owner by how it regards the player
+fn standing_style(owner: Owner, standing: Standing) -> Style {
+    match (owner, standing) {
+        (Owner::Player, _) => Style::new().fg(Color::Cyan),
+        (_, Standing::Allied) => Style::new().fg(Color::Green),
+        (_, Standing::Neutral) => Style::new().fg(Color::Gray),
+        (_, Standing::Hostile) => Style::new().fg(Color::Red),
     }
 }